@@ -0,0 +1,114 @@
+use std::{fs, io, path::PathBuf};
+
+use antenna::stations::Station;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{FocusRegion, MainView, utils::get_data_dir};
+
+const STATE_FILE: &str = "state.json";
+
+/// How many recently played stations are kept
+const MAX_RECENT: usize = 20;
+
+/// A station that was played, kept with its URL so it can be played again
+/// even if it isn't in the station list (anymore)
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecentStation {
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    pub played: DateTime<Local>,
+}
+
+impl RecentStation {
+    pub fn station(&self) -> Station {
+        Station {
+            id: self.id.clone(),
+            url: self.url.clone(),
+            name: self.name.clone(),
+            country: "Local".to_string(),
+            votes: 0,
+        }
+    }
+}
+
+/// What is kept between runs, stored in the data directory
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default)]
+pub struct AppState {
+    /// Most recently played first
+    pub recent: Vec<RecentStation>,
+    pub search: String,
+    pub view: MainView,
+    pub focus: FocusRegion,
+    pub selected_station: usize,
+    pub table_offset: usize,
+}
+
+impl AppState {
+    pub fn path() -> PathBuf {
+        get_data_dir().join(STATE_FILE)
+    }
+
+    /// Read the stored state, the default one if there is none
+    pub fn load() -> io::Result<Self> {
+        match fs::read(Self::path()) {
+            Ok(data) => serde_json::from_slice(&data).map_err(io::Error::other),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let data = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
+        fs::write(Self::path(), data)
+    }
+
+    /// Put `station` first in the recently played ones
+    pub fn played(&mut self, station: &Station) {
+        self.recent.retain(|recent| recent.id != station.id);
+        self.recent.insert(
+            0,
+            RecentStation {
+                id: station.id.clone(),
+                name: station.name.clone(),
+                url: station.url.clone(),
+                played: Local::now(),
+            },
+        );
+        self.recent.truncate(MAX_RECENT);
+    }
+
+    /// Indices of the recently played stations in `stations`, most recent first
+    pub fn recent_stations(&self, stations: &[Station]) -> Vec<usize> {
+        self.recent
+            .iter()
+            .filter_map(|recent| stations.iter().position(|station| station.id == recent.id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recently_played() {
+        let stations: Vec<_> = (0..MAX_RECENT + 5)
+            .map(|i| Station::mock(&i.to_string(), "Local"))
+            .collect();
+        let mut state = AppState::default();
+
+        for station in &stations {
+            state.played(station);
+        }
+        state.played(&stations[MAX_RECENT]);
+
+        assert_eq!(state.recent.len(), MAX_RECENT);
+        assert_eq!(state.recent[0].id, stations[MAX_RECENT].id);
+        assert_eq!(state.recent[1].id, stations[MAX_RECENT + 4].id);
+        assert_eq!(state.recent_stations(&stations[..2]), Vec::<usize>::new());
+        assert_eq!(state.recent[0].station(), stations[MAX_RECENT]);
+    }
+}
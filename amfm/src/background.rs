@@ -0,0 +1,168 @@
+use std::{
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
+
+use antenna::{
+    events::Subscription,
+    playback::{PlaybackManager, PlaybackOptions, PlaybackUpdate, TrackTags},
+    stations::Station,
+    title_parser::TitleParser,
+};
+
+use crate::{
+    segments::{Detector, Segment, SegmentKind},
+    song_queue::{Song, SongQueue, sanitize_filename},
+    title_rules::TitleRules,
+};
+
+/// Records a station into its own queue, without playing it
+pub struct BackgroundRecorder {
+    pub station: Station,
+    playback: PlaybackManager,
+    events: Subscription,
+    pub queue: SongQueue,
+    /// The title that is shown as recording, `None` if it is hidden
+    pub now_playing: Option<Arc<TrackTags>>,
+    /// Last error of the stream, cleared once it plays again
+    pub error: Option<String>,
+    /// Level statistics of the song that is being recorded
+    segment: Option<Segment>,
+    /// Wether the title changed since the recording started, the first song is joined in the middle
+    title_changed: bool,
+    /// Where the songs are recorded to
+    dir: PathBuf,
+}
+
+impl BackgroundRecorder {
+    /// Start recording `station`, its songs go into a directory of their own below `temp_dir`
+    pub fn start(
+        station: Station,
+        options: &PlaybackOptions,
+        parser: TitleParser,
+        max_queue_size: usize,
+        temp_dir: &Path,
+    ) -> Result<Self, Box<dyn Error>> {
+        let dir = temp_dir
+            .join("background")
+            .join(sanitize_filename(&station.id));
+        fs::create_dir_all(&dir)?;
+
+        let mut playback = PlaybackManager::new(&PlaybackOptions {
+            record_only: true,
+            ..options.clone()
+        })?;
+        let events = playback.subscribe();
+        playback.set_title_parser(parser);
+        playback.set_source_uri(&station.url)?;
+        playback.play()?;
+
+        Ok(Self {
+            station,
+            playback,
+            events,
+            queue: SongQueue::new(max_queue_size),
+            now_playing: None,
+            error: None,
+            segment: None,
+            title_changed: false,
+            dir,
+        })
+    }
+
+    /// The next update of the stream, if there is one
+    pub fn try_recv(&self) -> Option<PlaybackUpdate> {
+        self.events.try_recv().ok().map(|event| event.update)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.playback.is_recording()
+    }
+
+    /// Split the stream into songs, the same way the played station is
+    pub fn update(
+        &mut self,
+        update: PlaybackUpdate,
+        rules: &TitleRules,
+        detector: &Detector,
+    ) -> Result<(), Box<dyn Error>> {
+        match update {
+            PlaybackUpdate::Playing => self.error = None,
+            PlaybackUpdate::Error(err) => self.error = Some(err),
+            PlaybackUpdate::Level(level) => {
+                // Nothing is played, so the level is measured at the original volume
+                if let Some(segment) = &mut self.segment {
+                    segment.add_level(detector, &level, 1.0);
+                }
+            }
+            PlaybackUpdate::NewSong(tags) => {
+                self.playback.stop_recording(true);
+
+                // Now that the last recording is over, check if it actually was a song
+                if let Some(segment) = self.segment.take() {
+                    let kind = detector.classify_segment(&segment, Instant::now());
+                    if kind != SegmentKind::Music {
+                        self.queue.discard_last(kind)?;
+                    }
+                }
+
+                let decision = rules.decide(&self.station.id, &tags);
+                self.now_playing = decision.display.then(|| Arc::clone(&tags));
+
+                let mut song = Song::new(tags, &self.dir);
+                song.kind = detector.classify_tags(&song.tags);
+
+                if decision.is_song && !self.queue.song_exists(&song.tags.title) {
+                    // Ads are only listed, not recorded
+                    if song.kind == SegmentKind::Music && decision.record {
+                        self.playback.start_recording(&song.path)?;
+                        self.segment = Some(Segment::new(Instant::now(), !self.title_changed));
+                    }
+                    self.queue.insert(song)?;
+                }
+                self.title_changed = true;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Move every finished song to `dir`, returns how many were saved
+    pub fn save_songs(&mut self, dir: &Path) -> io::Result<usize> {
+        // The newest song is incomplete while it is still being recorded
+        let skip = usize::from(self.is_recording());
+        let finished: Vec<usize> = self
+            .queue
+            .iter()
+            .enumerate()
+            .skip(skip)
+            .filter(|(_, song)| song.path.exists())
+            .map(|(index, _)| index)
+            .collect();
+
+        // Removing from the back keeps the other indices valid
+        for &index in finished.iter().rev() {
+            if let Some(song) = self.queue.get(index) {
+                let name = song
+                    .path
+                    .file_name()
+                    .expect("Song should have valid filename");
+                fs::rename(&song.path, dir.join(name))?;
+                self.queue.remove(index);
+            }
+        }
+
+        Ok(finished.len())
+    }
+}
+
+impl Drop for BackgroundRecorder {
+    fn drop(&mut self) {
+        // The song that is being recorded is incomplete
+        self.playback.stop_recording(true);
+    }
+}
@@ -1,7 +1,24 @@
-use std::{env, fs, path::PathBuf};
+use std::{env, fs, path::PathBuf, time::Duration};
 
-use antenna::stations::Station;
-use clap::Parser;
+use antenna::{
+    hls::VariantPolicy,
+    network::{DEFAULT_USER_AGENT, NetworkSettings},
+    output::AudioSink,
+    playback::{BufferSettings, PlaybackOptions, SilenceTrim},
+    scrobble::{self, ScrobbleSettings},
+    stations::Station,
+    title_parser::DEFAULT_SEPARATORS,
+};
+use clap::{Parser, ValueEnum};
+use regex::Regex;
+
+use crate::{
+    segments::DEFAULT_AD_PATTERNS,
+    utils::{case_insensitive, get_data_dir},
+};
+
+/// Listens that weren't scrobbled yet, in the data directory
+const SCROBBLE_QUEUE_FILE: &str = "scrobble_queue.jsonl";
 
 fn get_default_temp_dir() -> PathBuf {
     let dir = env::temp_dir().join("amfm");
@@ -20,6 +37,57 @@ fn get_default_save_directory() -> PathBuf {
     dir
 }
 
+/// Parses a `Name: value` HTTP header
+fn header(header: &str) -> Result<(String, String), String> {
+    let (name, value) = header
+        .split_once(':')
+        .ok_or_else(|| format!("Expected `Name: value`, got `{header}`"))?;
+
+    Ok((name.trim().to_string(), value.trim().to_string()))
+}
+
+/// Kind of audio output to play to, see `Config::audio_sink`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SinkKind {
+    Auto,
+    Pulse,
+    Pipewire,
+    Alsa,
+    File,
+    /// Discard all audio
+    Fake,
+}
+
+/// Which variant of HLS streams to play, see `Config::hls_variant_policy`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HlsVariant {
+    /// Switch between variants by the available bandwidth
+    Auto,
+    /// An audio-only variant if there is one, the lowest bitrate one otherwise
+    AudioOnly,
+    Lowest,
+    Highest,
+}
+
+/// Buffering presets, see `Config::buffer_settings`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BufferProfile {
+    Default,
+    /// Start quickly and stay close to live, at the cost of more stalls
+    LowLatency,
+    /// Buffer a lot to ride out connection drops
+    FlakyNetwork,
+}
+
+/// What to do once the sleep timer runs out
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SleepAction {
+    /// Stop the playback
+    Stop,
+    /// Quit amfm
+    Quit,
+}
+
 #[derive(Parser, Debug)]
 #[command(version, about, long_about=None)]
 pub struct Config {
@@ -40,9 +108,133 @@ pub struct Config {
     #[arg(short, long)]
     pub initial_station: Option<String>,
 
+    /// Play the last station again, and restore the search, selection and focus of the last run
+    #[arg(long)]
+    pub resume: bool,
+
     /// Wether to record songs to disk
     #[arg(short, long, default_value_t = true)]
     pub record: bool,
+
+    /// Which audio output to play to
+    #[arg(long, value_enum, default_value_t = SinkKind::Auto)]
+    pub audio_sink: SinkKind,
+
+    /// Device of the audio output (ALSA/PulseAudio/PipeWire device name,
+    /// or the path of the WAV file for the `file` output)
+    #[arg(long)]
+    pub audio_device: Option<String>,
+
+    /// Show a spectrum visualizer in the info panel
+    #[arg(long)]
+    pub spectrum: bool,
+
+    /// Over how many seconds the sleep timer fades out the volume
+    #[arg(long, default_value_t = 30)]
+    pub sleep_fade: u64,
+
+    /// What to do once the sleep timer runs out
+    #[arg(long, value_enum, default_value_t = SleepAction::Stop)]
+    pub sleep_action: SleepAction,
+
+    /// Titles matching this (case insensitive) regex are treated as ads and not recorded.
+    /// Can be given multiple times, replacing the default patterns
+    #[arg(long = "ad-pattern", default_values = DEFAULT_AD_PATTERNS, value_parser = case_insensitive)]
+    pub ad_patterns: Vec<Regex>,
+
+    /// Level (dB) below which audio counts as silence
+    #[arg(long, default_value_t = -50, allow_negative_numbers = true)]
+    pub silence_threshold: i32,
+
+    /// Recordings shorter than this (in seconds) are treated as jingles and not kept
+    #[arg(long, default_value_t = 45)]
+    pub min_song_length: u64,
+
+    /// Silence longer than this (in seconds) is trimmed from the start and the end of song recordings.
+    /// Pauses within a song are kept, 0 disables trimming
+    #[arg(long, default_value_t = 2)]
+    pub trim_silence: u64,
+
+    /// Separator between artist and title in stream titles.
+    /// Can be given multiple times, replacing the default separators
+    #[arg(long = "title-separator", default_values = DEFAULT_SEPARATORS)]
+    pub title_separators: Vec<String>,
+
+    /// Strip markers like "(Radio Edit)" and trailing URLs from titles
+    #[arg(long)]
+    pub strip_title_junk: bool,
+
+    /// How often (in seconds) to ask the server what is playing, for stations that don't
+    /// send titles in their stream. 0 disables asking
+    #[arg(long, default_value_t = 15)]
+    pub now_playing_interval: u64,
+
+    /// Which variant of HLS streams to play
+    #[arg(long, value_enum, default_value_t = HlsVariant::AudioOnly)]
+    pub hls_variant: HlsVariant,
+
+    /// How much of streams to buffer
+    #[arg(long, value_enum, default_value_t = BufferProfile::Default)]
+    pub buffer_profile: BufferProfile,
+
+    /// How many seconds of streams to buffer, overriding the buffer profile
+    #[arg(long)]
+    pub buffer_duration: Option<f64>,
+
+    /// How many KiB of streams to buffer at most, overriding the buffer profile
+    #[arg(long)]
+    pub buffer_size: Option<u32>,
+
+    /// Proxy for the station list and streams, e.g. `http://proxy:3128` or `socks5://proxy:1080`.
+    /// Without it, the `http_proxy` environment variables are used
+    #[arg(long)]
+    pub proxy: Option<String>,
+
+    /// User-Agent sent to the station list and streams
+    #[arg(long, default_value_t = format!("amfm/{} ({DEFAULT_USER_AGENT})", env!("CARGO_PKG_VERSION")))]
+    pub user_agent: String,
+
+    /// Extra HTTP header (`Name: value`) sent to the station list and streams.
+    /// Can be given multiple times
+    #[arg(long = "header", value_parser = header)]
+    pub headers: Vec<(String, String)>,
+
+    /// How many seconds connecting to a server may take
+    #[arg(long, default_value_t = 10)]
+    pub connect_timeout: u64,
+
+    /// How many seconds a server may take to send anything
+    #[arg(long, default_value_t = 15)]
+    pub network_timeout: u64,
+
+    /// PEM file with additional certificate authorities to trust
+    #[arg(long)]
+    pub ca_bundle: Option<PathBuf>,
+
+    /// Ring the terminal bell when a wished song is on a watched station
+    #[arg(long)]
+    pub wishlist_bell: bool,
+
+    /// Show a desktop notification (through `notify-send`) when a wished song is on a watched station
+    #[arg(long)]
+    pub wishlist_notify: bool,
+
+    /// Record wished songs in the background as soon as they are on a watched station
+    #[arg(long)]
+    pub wishlist_record: bool,
+
+    /// ListenBrainz user token, scrobbling is off without one.
+    /// Prefer the environment variable, arguments show up in the process list
+    #[arg(long, env = "LISTENBRAINZ_TOKEN", hide_env_values = true)]
+    pub listenbrainz_token: Option<String>,
+
+    /// Server to scrobble to, anything that implements the ListenBrainz API
+    #[arg(long, default_value = scrobble::DEFAULT_URL)]
+    pub scrobble_url: String,
+
+    /// How many seconds a song has to be listened to before it is scrobbled
+    #[arg(long, default_value_t = 30)]
+    pub scrobble_min_listen: u64,
 }
 
 impl Config {
@@ -59,4 +251,91 @@ impl Config {
 
         None
     }
+
+    pub fn silence_trim(&self) -> Option<SilenceTrim> {
+        (self.trim_silence > 0).then(|| SilenceTrim {
+            threshold: self.silence_threshold,
+            min_duration: Duration::from_secs(self.trim_silence),
+        })
+    }
+
+    pub fn network_settings(&self) -> NetworkSettings {
+        NetworkSettings {
+            proxy: self.proxy.clone(),
+            user_agent: self.user_agent.clone(),
+            headers: self.headers.clone(),
+            connect_timeout: Duration::from_secs(self.connect_timeout),
+            timeout: Duration::from_secs(self.network_timeout),
+            ca_bundle: self.ca_bundle.clone(),
+        }
+    }
+
+    pub fn buffer_settings(&self) -> BufferSettings {
+        let profile = match self.buffer_profile {
+            BufferProfile::Default => BufferSettings::DEFAULT,
+            BufferProfile::LowLatency => BufferSettings::LOW_LATENCY,
+            BufferProfile::FlakyNetwork => BufferSettings::FLAKY_NETWORK,
+        };
+
+        BufferSettings {
+            duration: self.buffer_duration.map_or(profile.duration, |secs| {
+                Duration::from_secs_f64(secs.max(0.0))
+            }),
+            size: self
+                .buffer_size
+                .map(|kib| kib.saturating_mul(1024))
+                .or(profile.size),
+            ..profile
+        }
+    }
+
+    pub const fn hls_variant_policy(&self) -> VariantPolicy {
+        match self.hls_variant {
+            HlsVariant::Auto => VariantPolicy::Auto,
+            HlsVariant::AudioOnly => VariantPolicy::AudioOnly,
+            HlsVariant::Lowest => VariantPolicy::Lowest,
+            HlsVariant::Highest => VariantPolicy::Highest,
+        }
+    }
+
+    /// Options of the playback pipeline, shared by background recorders
+    pub fn playback_options(&self) -> PlaybackOptions {
+        PlaybackOptions {
+            sink: self.audio_sink(),
+            spectrum: self.spectrum,
+            silence_trim: self.silence_trim(),
+            hls_variant: self.hls_variant_policy(),
+            buffer: self.buffer_settings(),
+            network: self.network_settings(),
+            record_only: false,
+        }
+    }
+
+    /// `None` if scrobbling is off
+    pub fn scrobble_settings(&self) -> Option<ScrobbleSettings> {
+        Some(ScrobbleSettings {
+            url: self.scrobble_url.clone(),
+            token: self.listenbrainz_token.clone()?,
+            min_listen: Duration::from_secs(self.scrobble_min_listen),
+            queue_path: get_data_dir().join(SCROBBLE_QUEUE_FILE),
+            retry_delay: Duration::from_secs(60),
+        })
+    }
+
+    pub fn audio_sink(&self) -> AudioSink {
+        let device = self.audio_device.clone();
+
+        match self.audio_sink {
+            SinkKind::Auto => AudioSink::Auto,
+            SinkKind::Pulse => AudioSink::Pulse { device },
+            SinkKind::Pipewire => AudioSink::Pipewire { device },
+            SinkKind::Alsa => AudioSink::Alsa {
+                device: device.unwrap_or_else(|| "default".to_string()),
+            },
+            SinkKind::File => {
+                AudioSink::File(device.unwrap_or_else(|| "amfm.wav".to_string()).into())
+            }
+            SinkKind::Fake => AudioSink::Fake,
+        }
+    }
 }
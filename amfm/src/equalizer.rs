@@ -0,0 +1,26 @@
+use std::{fs, io, path::PathBuf};
+
+use antenna::equalizer::Gains;
+
+use crate::utils::get_data_dir;
+
+const EQUALIZER_FILE: &str = "equalizer.json";
+
+pub fn path() -> PathBuf {
+    get_data_dir().join(EQUALIZER_FILE)
+}
+
+/// Read the band gains the user had last time, if there are any
+pub fn load() -> io::Result<Option<Gains>> {
+    match fs::read(path()) {
+        Ok(data) => serde_json::from_slice(&data).map_err(io::Error::other),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Store the band gains, so they get restored on the next launch
+pub fn save(gains: &Gains) -> io::Result<()> {
+    let data = serde_json::to_vec(gains).map_err(io::Error::other)?;
+    fs::write(path(), data)
+}
@@ -0,0 +1,103 @@
+use std::{fs, io, path::PathBuf};
+
+use antenna::stations::Station;
+
+use crate::utils::get_data_dir;
+
+const FAVORITES_FILE: &str = "favorites.json";
+
+/// Stations the user marked, by id and in their own order.
+/// Stored in the data directory, so they survive rebuilding the station cache
+#[derive(Debug, Default)]
+pub struct Favorites {
+    ids: Vec<String>,
+}
+
+impl Favorites {
+    pub fn path() -> PathBuf {
+        get_data_dir().join(FAVORITES_FILE)
+    }
+
+    /// Read the stored favorites, none if there are none
+    pub fn load() -> io::Result<Self> {
+        let ids = match fs::read(Self::path()) {
+            Ok(data) => serde_json::from_slice(&data).map_err(io::Error::other)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => vec![],
+            Err(err) => return Err(err),
+        };
+
+        Ok(Self { ids })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let data = serde_json::to_vec_pretty(&self.ids).map_err(io::Error::other)?;
+        fs::write(Self::path(), data)
+    }
+
+    pub fn contains(&self, station_id: &str) -> bool {
+        self.ids.iter().any(|id| id == station_id)
+    }
+
+    /// Add or remove a station, returns wether it is a favorite now
+    pub fn toggle(&mut self, station_id: &str) -> bool {
+        if self.contains(station_id) {
+            self.ids.retain(|id| id != station_id);
+            false
+        } else {
+            self.ids.push(station_id.to_string());
+            true
+        }
+    }
+
+    /// Move a favorite one place up or down, returns wether it moved
+    pub fn shift(&mut self, station_id: &str, up: bool) -> bool {
+        let Some(index) = self.ids.iter().position(|id| id == station_id) else {
+            return false;
+        };
+
+        let other = if up {
+            index.checked_sub(1)
+        } else {
+            Some(index + 1).filter(|&other| other < self.ids.len())
+        };
+
+        other.is_some_and(|other| {
+            self.ids.swap(index, other);
+            true
+        })
+    }
+
+    /// Indices of the favorite stations in `stations`, in order.
+    /// Favorites that aren't in the list (anymore) are left out
+    pub fn stations(&self, stations: &[Station]) -> Vec<usize> {
+        self.ids
+            .iter()
+            .filter_map(|id| stations.iter().position(|station| station.id == *id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ordering() {
+        let stations = ["a", "b", "c"].map(|id| Station::mock(id, "Local"));
+        let mut favorites = Favorites::default();
+
+        assert!(favorites.toggle("c"));
+        assert!(favorites.toggle("a"));
+        assert!(favorites.toggle("gone"));
+        assert_eq!(favorites.stations(&stations), [2, 0]);
+
+        assert!(favorites.shift("a", true));
+        assert!(!favorites.shift("a", true));
+        assert!(!favorites.shift("gone", false));
+        assert_eq!(favorites.stations(&stations), [0, 2]);
+
+        assert!(!favorites.toggle("a"));
+        assert!(!favorites.contains("a"));
+        assert_eq!(favorites.stations(&stations), [2]);
+    }
+}
@@ -0,0 +1,241 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use antenna::playback::TrackTags;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::get_data_dir;
+
+const HISTORY_FILE: &str = "history.jsonl";
+
+/// What became of a track that was heard
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackStatus {
+    #[default]
+    Heard,
+    Recorded,
+    /// Recorded and saved permanently
+    Saved,
+}
+
+/// A track that was on air while listening to a station
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub time: DateTime<Local>,
+    pub station_id: String,
+    pub station_name: String,
+    pub artist: Option<String>,
+    pub title: String,
+    #[serde(default)]
+    pub status: TrackStatus,
+}
+
+impl HistoryEntry {
+    pub fn new(
+        station_id: &str,
+        station_name: &str,
+        tags: &TrackTags,
+        status: TrackStatus,
+    ) -> Self {
+        Self {
+            time: Local::now(),
+            station_id: station_id.to_string(),
+            station_name: station_name.to_string(),
+            artist: tags.artist.clone(),
+            title: tags.title.clone(),
+            status,
+        }
+    }
+
+    /// The track as `TrackTags` show it
+    pub fn track(&self) -> String {
+        TrackTags {
+            title: self.title.clone(),
+            artist: self.artist.clone(),
+        }
+        .to_string()
+    }
+
+    /// Case insensitive search in the track and the station name
+    fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        self.track().to_lowercase().contains(&query)
+            || self.station_name.to_lowercase().contains(&query)
+    }
+}
+
+/// Every track that was heard, stored as JSON lines in the data directory
+#[derive(Debug)]
+pub struct History {
+    path: PathBuf,
+    entries: Vec<HistoryEntry>,
+    /// Lines that can't be read, with the amount of entries before them.
+    /// They are written back as they were when the file is rewritten
+    unreadable: Vec<(usize, String)>,
+}
+
+impl History {
+    pub fn path() -> PathBuf {
+        get_data_dir().join(HISTORY_FILE)
+    }
+
+    /// Read the stored history, lines that can't be read are skipped but kept in the file
+    pub fn load() -> io::Result<Self> {
+        Self::load_from(Self::path())
+    }
+
+    fn load_from(path: PathBuf) -> io::Result<Self> {
+        let data = match fs::read_to_string(&path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+
+        let mut entries = vec![];
+        let mut unreadable = vec![];
+        for line in data.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str(line) {
+                Ok(entry) => entries.push(entry),
+                Err(_) => unreadable.push((entries.len(), line.to_string())),
+            }
+        }
+
+        Ok(Self {
+            path,
+            entries,
+            unreadable,
+        })
+    }
+
+    /// Add an entry, appending it to the file
+    pub fn push(&mut self, entry: HistoryEntry) -> io::Result<()> {
+        let mut line = serde_json::to_string(&entry).map_err(io::Error::other)?;
+        line.push('\n');
+
+        self.entries.push(entry);
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())
+    }
+
+    /// Change what became of the newest entry of a track,
+    /// e.g. once it was saved or its recording was thrown away
+    pub fn set_status(&mut self, tags: &TrackTags, status: TrackStatus) -> io::Result<()> {
+        let Some(entry) = self
+            .entries
+            .iter_mut()
+            .rev()
+            .find(|entry| entry.title == tags.title && entry.artist == tags.artist)
+        else {
+            return Ok(());
+        };
+
+        entry.status = status;
+        self.rewrite()
+    }
+
+    /// Write all entries again, replacing the file at once.
+    /// Lines that couldn't be read stay where they were
+    fn rewrite(&self) -> io::Result<()> {
+        let mut data = String::new();
+        let mut unreadable = self.unreadable.iter().peekable();
+        for (index, entry) in self.entries.iter().enumerate() {
+            while let Some((_, line)) = unreadable.next_if(|(before, _)| *before == index) {
+                data.push_str(line);
+                data.push('\n');
+            }
+            data.push_str(&serde_json::to_string(entry).map_err(io::Error::other)?);
+            data.push('\n');
+        }
+        for (_, line) in unreadable {
+            data.push_str(line);
+            data.push('\n');
+        }
+
+        let temp = self.path.with_extension("jsonl.tmp");
+        fs::write(&temp, data)?;
+        fs::rename(temp, &self.path)
+    }
+
+    /// Entries matching `query`, newest first
+    pub fn search<'a>(&'a self, query: &'a str) -> impl Iterator<Item = &'a HistoryEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(move |entry| query.is_empty() || entry.matches(query))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn persists_entries() {
+        let path = env::temp_dir().join(format!("amfm-history-{}.jsonl", process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut history = History::load_from(path.clone()).unwrap();
+        let first = TrackTags::mock("One More Time", Some("Daft Punk"));
+        let second = TrackTags::mock("Jóga", Some("Björk"));
+        history
+            .push(HistoryEntry::new(
+                "fip",
+                "FIP",
+                &first,
+                TrackStatus::Recorded,
+            ))
+            .unwrap();
+        history
+            .push(HistoryEntry::new(
+                "nts",
+                "NTS 1",
+                &second,
+                TrackStatus::Heard,
+            ))
+            .unwrap();
+        history.set_status(&first, TrackStatus::Saved).unwrap();
+
+        let history = History::load_from(path.clone()).unwrap();
+        let tracks: Vec<_> = history.search("").map(HistoryEntry::track).collect();
+        assert_eq!(tracks, ["Björk - Jóga", "Daft Punk - One More Time"]);
+
+        let found: Vec<_> = history.search("daft").collect();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].status, TrackStatus::Saved);
+        assert_eq!(history.search("nts").count(), 1);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn keeps_unreadable_lines() {
+        let path = env::temp_dir().join(format!("amfm-history-{}-broken.jsonl", process::id()));
+        let tags = TrackTags::mock("Teardrop", Some("Massive Attack"));
+        let entry = HistoryEntry::new("fip", "FIP", &tags, TrackStatus::Recorded);
+        let line = serde_json::to_string(&entry).unwrap();
+        fs::write(&path, format!("{{\"time\": \n{line}\nnot json\n")).unwrap();
+
+        let mut history = History::load_from(path.clone()).unwrap();
+        assert_eq!(history.search("").count(), 1);
+        history.set_status(&tags, TrackStatus::Saved).unwrap();
+
+        let data = fs::read_to_string(&path).unwrap();
+        let lines: Vec<_> = data.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "{\"time\": ");
+        assert!(lines[1].contains("\"saved\""));
+        assert_eq!(lines[2], "not json");
+
+        fs::remove_file(path).unwrap();
+    }
+}
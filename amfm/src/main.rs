@@ -1,38 +1,72 @@
 use std::{
     error::Error,
     fs,
-    sync::{
-        Arc,
-        mpsc::{self, Receiver},
-    },
-    time::Duration,
+    io::{self, Write},
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 use antenna::{
     cache::{self, CacheResult},
-    playback::{PlaybackManager, PlaybackUpdate},
+    equalizer::{self as eq, Preset},
+    events::Subscription,
+    monitor::{MonitorEvent, StationMonitor},
+    now_playing,
+    output::{self, AudioSink, OutputDevice},
+    playback::{AudioLevel, PlaybackError, PlaybackManager, PlaybackUpdate, StreamInfo, TrackTags},
+    scrobble::Scrobbler,
     stations::{Station, StationList},
+    title_parser::TitleParser,
 };
 
 use arboard::Clipboard;
+use chrono::{Local, NaiveDateTime};
 use clap::Parser;
 use ratatui::{
-    crossterm::event::{self, Event, KeyCode},
+    crossterm::event::{self, Event, KeyCode, KeyModifiers},
     widgets::{ListState, TableState},
 };
+use serde::{Deserialize, Serialize};
 use tui_input::{Input, backend::crossterm::EventHandler};
 
 use crate::{
-    config::Config,
+    app_state::{AppState, RecentStation},
+    background::BackgroundRecorder,
+    config::{Config, SleepAction},
+    favorites::Favorites,
+    history::{History, HistoryEntry, TrackStatus},
+    schedule::{ActiveRecording, Schedule, ScheduleEntry},
     screens::{Screen, loading::LoadingScreen, play::PlayScreen},
-    song_queue::{Song, SongQueue},
+    segments::{Detector, Segment, SegmentKind},
+    sleep_timer::{SleepTimer, TimerState},
+    song_queue::{Song, SongQueue, sanitize_filename},
+    title_rules::TitleRules,
+    wishlist::{Wishlist, WishlistAlert},
 };
 
+mod app_state;
+mod background;
 mod config;
+mod equalizer;
+mod favorites;
+mod history;
+mod schedule;
 mod screens;
+mod segments;
+mod sleep_timer;
 mod song_queue;
+mod title_rules;
 mod utils;
 mod widgets;
+mod wishlist;
+
+/// How many songs a queue keeps
+const QUEUE_SIZE: usize = 10;
+
+/// How often time based features (sleep timer, schedule) catch up, even while events keep coming
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
 
 pub struct AppModel {
     pub running_state: RunningState,
@@ -51,25 +85,85 @@ pub struct AppModel {
 
     pub last_selected_station: usize,
 
+    pub view: MainView,
+    pub favorites: Favorites,
+    /// Recently played stations and the UI state of the last run
+    pub app_state: AppState,
+
     pub playback: PlaybackManager,
-    pub playback_receiver: Receiver<PlaybackUpdate>,
+    pub playback_events: Subscription,
 
     pub current_station: Option<usize>,
 
     pub queue: SongQueue,
     pub queue_list_state: ListState,
+    /// The title that is shown as playing, `None` if it is hidden
+    pub now_playing: Option<Arc<TrackTags>>,
+
+    pub title_rules: TitleRules,
+
+    pub detector: Detector,
+    /// Level statistics of the song that is being recorded
+    pub segment: Option<Segment>,
+    /// Wether the title changed since the station started playing.
+    /// The first title is usually joined half way
+    pub title_changed: bool,
 
     pub focus: FocusRegion,
+    pub popup: Option<Popup>,
 
     pub config: Config,
 
+    pub audio_sink: AudioSink,
+    pub outputs: Vec<OutputDevice>,
+    pub outputs_list_state: ListState,
+
+    pub equalizer_band: usize,
+
+    pub sleep_timer: Option<SleepTimer>,
+    /// When time based features last caught up
+    pub last_tick: Instant,
+    pub sleep_timer_list_state: ListState,
+    pub sleep_timer_input: Input,
+
+    pub schedule: Schedule,
+    pub schedule_list_state: ListState,
+    /// The schedule being typed in, and the index of the entry it replaces
+    pub schedule_input: Option<(Input, Option<usize>)>,
+    pub schedule_error: Option<String>,
+    pub scheduled_recording: Option<ActiveRecording>,
+    /// Occurrence that was last recorded, so it doesn't start again once it was stopped
+    pub last_scheduled: Option<(ScheduleEntry, NaiveDateTime)>,
+
+    /// Stations that are recorded next to the one being played
+    pub background: Vec<BackgroundRecorder>,
+    pub background_list_state: ListState,
+
+    pub wishlist: Wishlist,
+    pub wishlist_list_state: ListState,
+    pub wishlist_input: Option<Input>,
+    pub wishlist_error: Option<String>,
+    /// Watches the titles of the wishlist stations, `None` if it couldn't be set up
+    pub monitor: Option<StationMonitor>,
+    pub wishlist_alert: Option<WishlistAlert>,
+
+    pub history: History,
+    pub history_list_state: ListState,
+    pub history_search: Input,
+
+    /// `None` if scrobbling is off
+    pub scrobbler: Option<Scrobbler>,
+
     pub last_update: PlaybackUpdate,
+    pub stream_info: Option<StreamInfo>,
+    pub level: Option<AudioLevel>,
+    pub spectrum: Option<Vec<f32>>,
 
     pub clipboard: Option<Clipboard>,
 }
 
 impl AppModel {
-    fn new() -> Self {
+    fn new(config: Config) -> Result<Self, Box<dyn Error>> {
         let data = cache::read_bin_cache();
 
         let screen;
@@ -83,39 +177,117 @@ impl AppModel {
         } else {
             stations = vec![];
             screen = Screen::Loading;
-            loading_result = Some(cache::make_cache());
+            loading_result = Some(cache::make_cache(&config.network_settings()));
+        }
+
+        let audio_sink = config.audio_sink();
+
+        PlaybackManager::init()?;
+        let mgr = PlaybackManager::new(&config.playback_options())?;
+        let playback_events = mgr.subscribe();
+        let monitor = StationMonitor::new(&config.network_settings()).ok();
+        let scrobbler = config
+            .scrobble_settings()
+            .map(|settings| Scrobbler::new(settings, &config.network_settings()))
+            .transpose()?;
+
+        let detector = Detector::new(
+            config.ad_patterns.clone(),
+            config.silence_threshold.into(),
+            Duration::from_secs(config.min_song_length),
+        );
+
+        let gains = equalizer::load()
+            .map_err(|err| format!("Invalid equalizer ({}): {err}", equalizer::path().display()))?;
+        if let Some(gains) = gains {
+            mgr.set_equalizer(&gains)?;
         }
 
-        let (tx, rx) = mpsc::channel();
+        let title_rules = TitleRules::load().map_err(|err| {
+            format!(
+                "Invalid title rules ({}): {err}",
+                TitleRules::path().display()
+            )
+        })?;
+
+        let schedule = Schedule::load()
+            .map_err(|err| format!("Invalid schedule ({}): {err}", Schedule::path().display()))?;
 
-        PlaybackManager::init();
-        let mgr = PlaybackManager::new(tx);
+        let favorites = Favorites::load()
+            .map_err(|err| format!("Invalid favorites ({}): {err}", Favorites::path().display()))?;
+
+        let app_state = AppState::load()
+            .map_err(|err| format!("Invalid app state ({}): {err}", AppState::path().display()))?;
+
+        let history = History::load()
+            .map_err(|err| format!("Invalid history ({}): {err}", History::path().display()))?;
+
+        let wishlist = Wishlist::load()
+            .map_err(|err| format!("Invalid wishlist ({}): {err}", Wishlist::path().display()))?;
 
         let mut stations_table_state = TableState::default();
         stations_table_state.select(Some(0));
 
-        Self {
+        Ok(Self {
             stations,
             running_state: RunningState::Running,
             screen,
             loading_percentage: 0,
             loading_result,
             playback: mgr,
-            playback_receiver: rx,
+            playback_events,
             current_station: None,
             stations_search: "".into(),
             last_selected_station: 0,
-            queue: SongQueue::new(10),
+            view: MainView::Stations,
+            favorites,
+            app_state,
+            queue: SongQueue::new(QUEUE_SIZE),
             queue_list_state: ListState::default(),
+            now_playing: None,
+            title_rules,
+            detector,
+            segment: None,
+            title_changed: false,
             stations_table_state,
             focus: FocusRegion::MainArea,
-            config: Config::parse(),
+            popup: None,
+            config,
+            audio_sink,
+            outputs: vec![],
+            outputs_list_state: ListState::default(),
+            equalizer_band: 0,
+            sleep_timer: None,
+            last_tick: Instant::now(),
+            sleep_timer_list_state: ListState::default(),
+            sleep_timer_input: "".into(),
+            schedule,
+            schedule_list_state: ListState::default(),
+            schedule_input: None,
+            schedule_error: None,
+            scheduled_recording: None,
+            last_scheduled: None,
+            background: vec![],
+            background_list_state: ListState::default(),
+            wishlist,
+            wishlist_list_state: ListState::default(),
+            wishlist_input: None,
+            wishlist_error: None,
+            monitor,
+            wishlist_alert: None,
+            history,
+            history_list_state: ListState::default(),
+            history_search: "".into(),
+            scrobbler,
             search_toggled: false,
             last_update: PlaybackUpdate::Loading,
+            stream_info: None,
+            level: None,
+            spectrum: None,
             table_size: 0,
             table_virtual_offset: 0,
             clipboard: Clipboard::new().ok(),
-        }
+        })
     }
 }
 
@@ -131,15 +303,73 @@ enum Message {
     SearchEvent(Event),
     CopyStationURL,
     StopPlayback,
+    OpenOutputPicker,
+    OpenEqualizer,
+    NextEqualizerPreset,
+    OpenSleepTimer,
+    SleepTimerInput(Event),
+    CancelSleepTimer,
+    Tick,
+    OpenSchedule,
+    EditSchedule(bool),
+    ScheduleInput(Event),
+    DeleteSchedule,
+    ToggleSessionRecording,
+    /// An update of the background recorder of a station, by station id
+    BackgroundMsg(String, PlaybackUpdate),
+    ToggleBackgroundRecording,
+    OpenBackgroundRecorders,
+    StopBackgroundRecording,
+    MonitorMsg(MonitorEvent),
+    ToggleWatchStation,
+    OpenWishlist,
+    EditWishlist,
+    WishlistInput(Event),
+    DeleteWishlistPattern,
+    TuneIn,
+    DismissAlert,
+    OpenHistory,
+    HistoryInput(Event),
+    CopyHistoryTrack,
+    ToggleFavorite,
+    /// Show another list in the main area, or all stations if it is shown already
+    SwitchView(MainView),
+    /// Move the selected favorite up (`true`) or down
+    MoveFavorite(bool),
+    /// Play one of the first ten favorites
+    QuickPlay(usize),
+    ClosePopup,
+}
+
+/// What the main area lists
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MainView {
+    #[default]
+    Stations,
+    Favorites,
+    Recent,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum FocusRegion {
+    #[default]
     MainArea,
     RadioInfo,
     Queue,
 }
 
+/// Windows that are drawn on top of the play screen and take all key input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Popup {
+    OutputPicker,
+    Equalizer,
+    SleepTimer,
+    Schedule,
+    BackgroundRecorders,
+    Wishlist,
+    History,
+}
+
 #[derive(PartialEq, Eq, Debug)]
 pub enum RunningState {
     Running,
@@ -147,14 +377,34 @@ pub enum RunningState {
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+    // Create the model before taking over the terminal, so that
+    // errors are printed normally
+    let mut model = AppModel::new(Config::parse())?;
     let mut terminal = ratatui::init();
-    let mut model = AppModel::new();
 
-    let config = Config::parse();
+    watch_wishlist_stations(&mut model);
+
+    if model.config.resume {
+        restore_ui_state(&mut model);
+    }
+
+    // The station to start with, a URL or the last one played
+    let initial_station = model.config.station().or_else(|| {
+        model
+            .config
+            .resume
+            .then(|| model.app_state.recent.first().map(RecentStation::station))
+            .flatten()
+    });
 
-    if let Some(station) = config.station() {
-        model.stations.push(station);
-        let new_station_idx = model.stations.len() - 1;
+    if let Some(station) = initial_station {
+        let new_station_idx = match model.stations.iter().position(|s| s.id == station.id) {
+            Some(index) => index,
+            None => {
+                model.stations.push(station);
+                model.stations.len() - 1
+            }
+        };
 
         play_station(&mut model, new_station_idx);
     }
@@ -179,7 +429,17 @@ fn update(model: &mut AppModel, msg: Message) -> Option<Message> {
     match msg {
         Message::Quit => {
             model.running_state = {
-                model.playback.stop_recording(true);
+                // A session recording is kept, the shutdown waits until it is written
+                if !model.playback.is_session_recording() {
+                    model.playback.stop_recording(true);
+                }
+                model.playback.shutdown();
+                // Queued listens are submitted on the next start
+                if let Some(scrobbler) = &mut model.scrobbler {
+                    let _ = scrobbler.new_track(None);
+                }
+                model.background.clear();
+                save_ui_state(model);
                 fs::remove_dir_all(&model.config.temp_song_location)
                     .expect("Could not delete temporary directory");
                 RunningState::Done
@@ -189,29 +449,127 @@ fn update(model: &mut AppModel, msg: Message) -> Option<Message> {
         Message::ChangeScreen(screen) => model.screen = screen,
         Message::LoadCache => {
             model.stations = cache::read_bin_cache().expect("Should have been able to read cache");
+            watch_wishlist_stations(model);
 
             return Some(Message::ChangeScreen(Screen::Play));
         }
+        Message::PlaybackMsg(PlaybackUpdate::StreamInfo(info)) => model.stream_info = Some(info),
+        Message::PlaybackMsg(PlaybackUpdate::Level(level)) => {
+            if let Some(segment) = &mut model.segment {
+                let volume = model.playback.volume().unwrap_or(1.0);
+                segment.add_level(&model.detector, &level, volume);
+            }
+            model.level = Some(level);
+        }
+        Message::PlaybackMsg(PlaybackUpdate::Spectrum(spectrum)) => model.spectrum = Some(spectrum),
         Message::PlaybackMsg(msg) => {
             model.last_update = msg;
-            if let PlaybackUpdate::NewSong(tags) = &model.last_update {
+
+            let PlaybackUpdate::NewSong(tags) = &model.last_update else {
+                return None;
+            };
+            // Not borrowed from `last_update`, so errors can still be reported below
+            let tags = &Arc::clone(tags);
+
+            let station_id = model
+                .current_station
+                .map_or("", |index| model.stations[index].id.as_str());
+            let decision = model.title_rules.decide(station_id, tags);
+            model.now_playing = decision.display.then(|| Arc::clone(tags));
+
+            // Session and scheduled recordings go into a single file
+            if model.scheduled_recording.is_none() && !model.playback.is_session_recording() {
                 model.playback.stop_recording(true);
 
-                let song = Song::new(Arc::clone(tags), model.config.temp_song_location.clone());
+                // Now that the last recording is over, check if it actually was a song
+                if let Some(segment) = model.segment.take() {
+                    let kind = model.detector.classify_segment(&segment, Instant::now());
+                    if kind != SegmentKind::Music {
+                        let discarded = model.queue.last().map(|song| Arc::clone(&song.tags));
+
+                        // Its recording is gone, so it was only heard
+                        let result = model.queue.discard_last(kind).and_then(|()| {
+                            discarded.map_or(Ok(()), |tags| {
+                                model.history.set_status(&tags, TrackStatus::Heard)
+                            })
+                        });
+                        if let Err(err) = result {
+                            model.last_update = PlaybackUpdate::Error(format!(
+                                "Could not discard recording: {err}"
+                            ));
+                        }
+                    }
+                }
+
+                let mut song = Song::new(Arc::clone(tags), model.config.temp_song_location.clone());
+                song.kind = model.detector.classify_tags(tags);
+
+                if decision.is_song
+                    && model.config.record
+                    && !model.queue.song_exists(&song.tags.title)
+                {
+                    // Nothing new is recorded once the sleep timer ran out
+                    let sleeping = model.sleep_timer.as_ref().is_some_and(|timer| {
+                        matches!(
+                            timer.state(Instant::now()),
+                            TimerState::Expired | TimerState::Done
+                        )
+                    });
 
-                if model.config.record && !model.queue.song_exists(&song.tags.title) {
-                    model.playback.start_recording(&song.path);
+                    // Ads are only listed, not recorded
+                    if song.kind == SegmentKind::Music && decision.record && !sleeping {
+                        // The track is still listed, scrobbled and logged without a recording
+                        match model.playback.start_recording(&song.path) {
+                            Ok(()) => {
+                                model.segment =
+                                    Some(Segment::new(Instant::now(), !model.title_changed));
+                            }
+                            Err(err) => model.last_update = PlaybackUpdate::Error(err.to_string()),
+                        }
+                    }
 
                     model
                         .queue
                         .insert(song)
                         .expect("Error inserting new song to queue");
                 }
+
+                model.title_changed = true;
+            }
+
+            // Only music is scrobbled, once it was listened to long enough
+            let scrobble = decision.is_song
+                && model.detector.classify_tags(tags) == SegmentKind::Music
+                && model.title_rules.scrobbles(station_id);
+            if let Some(scrobbler) = &mut model.scrobbler
+                && let Err(err) = scrobbler.new_track(scrobble.then_some(tags.as_ref()))
+            {
+                model.last_update = PlaybackUpdate::Error(err.to_string());
+            }
+
+            // Hidden titles weren't heard as far as the user is concerned
+            if decision.display
+                && let Some(index) = model.current_station
+            {
+                let station = &model.stations[index];
+                let status = if model.playback.is_recording() {
+                    TrackStatus::Recorded
+                } else {
+                    TrackStatus::Heard
+                };
+                let entry = HistoryEntry::new(&station.id, &station.name, tags, status);
+
+                if let Err(err) = model.history.push(entry) {
+                    model.last_update =
+                        PlaybackUpdate::Error(format!("Could not write history: {err}"));
+                }
             }
         }
         Message::StopPlayback => stop(model),
         Message::Navigation(key) => {
-            if let Some(new_focus) = handle_navigation(model, key) {
+            if let Some(popup) = model.popup {
+                handle_popup_navigation(model, popup, key);
+            } else if let Some(new_focus) = handle_navigation(model, key) {
                 model.focus = new_focus;
                 model
                     .queue_list_state
@@ -226,12 +584,122 @@ fn update(model: &mut AppModel, msg: Message) -> Option<Message> {
                     .select(Some(model.last_selected_station));
             }
         }
+        Message::Selection if model.popup == Some(Popup::OutputPicker) => {
+            model.popup = None;
+
+            if let Some(output) = model
+                .outputs_list_state
+                .selected()
+                .and_then(|index| model.outputs.get(index))
+                .cloned()
+            {
+                end_recording(model);
+                match model.playback.set_sink(&output.sink) {
+                    Ok(()) => model.audio_sink = output.sink.clone(),
+                    Err(err) => model.last_update = PlaybackUpdate::Error(err.to_string()),
+                }
+            }
+        }
+        Message::Selection if model.popup == Some(Popup::SleepTimer) => {
+            model.popup = None;
+
+            let minutes = model.sleep_timer_input.value().parse().ok().or_else(|| {
+                model
+                    .sleep_timer_list_state
+                    .selected()
+                    .and_then(|index| sleep_timer::PRESETS.get(index).copied())
+            });
+
+            if let Some(minutes) = minutes {
+                model.sleep_timer = Some(SleepTimer::new(
+                    Duration::from_secs(minutes * 60),
+                    Duration::from_secs(model.config.sleep_fade),
+                    Instant::now(),
+                ));
+            }
+        }
+        Message::Selection if model.popup == Some(Popup::Schedule) => {
+            let Some((input, replace)) = model.schedule_input.take() else {
+                return Some(Message::EditSchedule(false));
+            };
+
+            // Edited entries keep their station, new ones use the current one
+            let (station_id, station_name) =
+                match replace.and_then(|index| model.schedule.get(index)) {
+                    Some(entry) => (entry.station_id.clone(), entry.station_name.clone()),
+                    None => match model.current_station {
+                        Some(index) => {
+                            let station = &model.stations[index];
+                            (station.id.clone(), station.name.clone())
+                        }
+                        None => return None,
+                    },
+                };
+
+            let today = Local::now().date_naive();
+            let entry = ScheduleEntry::parse(input.value(), &station_id, &station_name);
+            model.schedule_error = match entry {
+                Ok(entry) => match model.schedule.insert(entry, replace, today) {
+                    Ok(()) => model.schedule.save().err().map(|err| err.to_string()),
+                    Err(conflict) => Some(format!(
+                        "Conflicts with {} ({})",
+                        conflict.station_name,
+                        conflict.spec()
+                    )),
+                },
+                Err(err) => Some(err),
+            };
+
+            // Let the user fix the input
+            if model.schedule_error.is_some() {
+                model.schedule_input = Some((input, replace));
+            }
+        }
+        Message::Selection if model.popup == Some(Popup::History) => {
+            let entry = model.history_list_state.selected().and_then(|index| {
+                model
+                    .history
+                    .search(model.history_search.value())
+                    .nth(index)
+            })?;
+            let station = model
+                .stations
+                .iter()
+                .position(|station| station.id == entry.station_id)?;
+
+            model.popup = None;
+            play_station(model, station);
+        }
+        Message::Selection if model.popup == Some(Popup::Wishlist) => {
+            let input = model.wishlist_input.take()?;
+
+            model.wishlist_error = match model.wishlist.add_pattern(input.value()) {
+                Ok(()) => model.wishlist.save().err().map(|err| err.to_string()),
+                Err(err) => {
+                    // Let the user fix the input
+                    model.wishlist_input = Some(input);
+                    Some(err)
+                }
+            };
+        }
+        Message::Selection if model.popup == Some(Popup::BackgroundRecorders) => {
+            if let Some(recorder) = model
+                .background_list_state
+                .selected()
+                .and_then(|index| model.background.get_mut(index))
+                && let Err(err) = recorder.save_songs(&model.config.saved_song_location)
+            {
+                recorder.error = Some(format!("Could not save songs: {err}"));
+            }
+        }
         Message::Selection => {
             match model.focus {
                 FocusRegion::Queue => {
                     if let Some(index) = model.queue_list_state.selected()
                 && index != 0 // First song still being recorded
                 && let Some(song) = model.queue.get(index)
+                // Only recorded music is kept on disk
+                && song.path.exists()
                     {
                         // Save song permanently
                         fs::rename(
@@ -243,24 +711,15 @@ fn update(model: &mut AppModel, msg: Message) -> Option<Message> {
                             ),
                         )
                         .expect("Could not save song permanently!");
+                        let _ = model.history.set_status(&song.tags, TrackStatus::Saved);
 
                         // Remove from queue
                         model.queue.remove(index);
                     }
                 }
                 FocusRegion::MainArea => {
-                    if let Some(index) = model.stations_table_state.selected() {
-                        let station = {
-                            model
-                                .stations
-                                .search(model.stations_search.value())
-                                .skip(model.table_virtual_offset)
-                                .nth(index)
-                        };
-
-                        if let Some(station) = station {
-                            play_station(model, station);
-                        }
+                    if let Some(station) = selected_station(model) {
+                        play_station(model, station);
                     }
                 }
                 _ => {}
@@ -280,6 +739,323 @@ fn update(model: &mut AppModel, msg: Message) -> Option<Message> {
             model.table_virtual_offset = 0;
             model.stations_search.handle_event(&event);
         }
+        Message::OpenOutputPicker => {
+            model.outputs = vec![OutputDevice {
+                name: "Automatic".to_string(),
+                sink: AudioSink::Auto,
+            }];
+
+            match output::list_output_devices() {
+                Ok(devices) => model.outputs.extend(devices),
+                Err(err) => model.last_update = PlaybackUpdate::Error(err.to_string()),
+            }
+
+            model.outputs.push(OutputDevice {
+                name: "None (discard audio)".to_string(),
+                sink: AudioSink::Fake,
+            });
+
+            let current = model
+                .outputs
+                .iter()
+                .position(|output| output.sink == model.audio_sink);
+            model.outputs_list_state.select(current.or(Some(0)));
+            model.popup = Some(Popup::OutputPicker);
+        }
+        Message::OpenEqualizer => model.popup = Some(Popup::Equalizer),
+        Message::NextEqualizerPreset => {
+            let next = model
+                .playback
+                .equalizer()
+                .ok()
+                .and_then(|gains| Preset::matching(&gains))
+                .map_or(Preset::Flat, Preset::next);
+
+            if let Err(err) = model.playback.set_equalizer(&next.gains()) {
+                model.last_update = PlaybackUpdate::Error(err.to_string());
+            }
+        }
+        Message::OpenSleepTimer => {
+            model.sleep_timer_input.reset();
+            model.sleep_timer_list_state.select(Some(0));
+            model.popup = Some(Popup::SleepTimer);
+        }
+        Message::SleepTimerInput(event) => {
+            model.sleep_timer_input.handle_event(&event);
+        }
+        Message::CancelSleepTimer => {
+            if model.sleep_timer.take().is_some() {
+                let _ = model.playback.set_volume(1.0);
+            }
+        }
+        Message::Tick => {
+            model.last_tick = Instant::now();
+            let now = Local::now().naive_local();
+            if let Some(msg) = tick_schedule(model, now) {
+                return Some(msg);
+            }
+
+            return tick_sleep_timer(model, Instant::now());
+        }
+        Message::ToggleSessionRecording => {
+            if model.playback.is_session_recording() {
+                model.playback.stop_recording(false);
+                // Stopping a scheduled recording by hand ends it early
+                model.scheduled_recording = None;
+            } else if let Some(index) = model.current_station {
+                let name = model.stations[index].name.clone();
+                let path = recording_path(model, &name, Local::now().naive_local());
+
+                // Drop the song that was being split off, the session covers it
+                model.playback.stop_recording(true);
+                model.segment = None;
+                if let Err(err) = model.playback.start_session_recording(&path, &name) {
+                    return Some(Message::PlaybackMsg(PlaybackUpdate::Error(err.to_string())));
+                }
+            }
+        }
+        Message::BackgroundMsg(station_id, update) => {
+            if let Some(recorder) = model
+                .background
+                .iter_mut()
+                .find(|recorder| recorder.station.id == station_id)
+                && let Err(err) = recorder.update(update, &model.title_rules, &model.detector)
+            {
+                recorder.error = Some(err.to_string());
+            }
+        }
+        Message::ToggleBackgroundRecording => {
+            let station = selected_station(model)?;
+            let station = model.stations[station].clone();
+
+            if let Some(index) = model
+                .background
+                .iter()
+                .position(|recorder| recorder.station.id == station.id)
+            {
+                stop_background_recording(model, index);
+            } else {
+                start_background_recording(model, station);
+            }
+        }
+        Message::OpenBackgroundRecorders => {
+            model.background_list_state.select(Some(0));
+            model.popup = Some(Popup::BackgroundRecorders);
+        }
+        Message::StopBackgroundRecording => {
+            if let Some(index) = model.background_list_state.selected()
+                && index < model.background.len()
+            {
+                stop_background_recording(model, index);
+            }
+        }
+        Message::MonitorMsg(event) => {
+            let index = model
+                .stations
+                .iter()
+                .position(|station| station.id == event.station_id)?;
+
+            let Some(pattern) = model.wishlist.matching(&event.tags) else {
+                // The wished song is over
+                if model
+                    .wishlist_alert
+                    .as_ref()
+                    .is_some_and(|alert| alert.station_id == event.station_id)
+                {
+                    model.wishlist_alert = None;
+                }
+                return None;
+            };
+
+            // Already listening
+            if model.current_station == Some(index) {
+                return None;
+            }
+
+            let alert = WishlistAlert {
+                station_id: event.station_id,
+                station_name: model.stations[index].name.clone(),
+                tags: event.tags,
+                pattern: pattern.to_string(),
+            };
+            notify(&model.config, &alert);
+
+            if model.config.wishlist_record
+                && !model
+                    .background
+                    .iter()
+                    .any(|recorder| recorder.station.id == alert.station_id)
+            {
+                start_background_recording(model, model.stations[index].clone());
+            }
+
+            model.wishlist_alert = Some(alert);
+        }
+        Message::ToggleWatchStation => {
+            let station = selected_station(model)?;
+            let station = model.stations[station].clone();
+
+            if model.wishlist.toggle_station(&station) {
+                let parser = title_parser(model, &station.id);
+                if let Some(monitor) = &mut model.monitor {
+                    monitor.watch(&station, parser);
+                }
+            } else if let Some(monitor) = &mut model.monitor {
+                monitor.unwatch(&station.id);
+            }
+
+            if let Err(err) = model.wishlist.save() {
+                model.last_update = PlaybackUpdate::Error(err.to_string());
+            }
+        }
+        Message::OpenWishlist => {
+            model.wishlist_input = None;
+            model.wishlist_error = None;
+            model.wishlist_list_state.select(Some(0));
+            model.popup = Some(Popup::Wishlist);
+        }
+        Message::EditWishlist => {
+            model.wishlist_error = None;
+            model.wishlist_input = Some("".into());
+        }
+        Message::WishlistInput(event) => {
+            if let Some(input) = &mut model.wishlist_input {
+                input.handle_event(&event);
+            }
+        }
+        Message::DeleteWishlistPattern => {
+            if let Some(index) = model.wishlist_list_state.selected() {
+                model.wishlist.remove_pattern(index);
+                model.wishlist_error = model.wishlist.save().err().map(|err| err.to_string());
+            }
+        }
+        Message::TuneIn => {
+            let alert = model.wishlist_alert.take()?;
+            if let Some(index) = model
+                .stations
+                .iter()
+                .position(|station| station.id == alert.station_id)
+            {
+                play_station(model, index);
+            }
+        }
+        Message::DismissAlert => model.wishlist_alert = None,
+        Message::ToggleFavorite => {
+            let station = selected_station(model)?;
+            model.favorites.toggle(&model.stations[station].id);
+            if let Err(err) = model.favorites.save() {
+                model.last_update = PlaybackUpdate::Error(err.to_string());
+            }
+        }
+        Message::SwitchView(view) => {
+            model.view = if model.view == view {
+                MainView::Stations
+            } else {
+                view
+            };
+            model.focus = FocusRegion::MainArea;
+            model.table_virtual_offset = 0;
+            model.last_selected_station = 0;
+            model.stations_table_state.select(Some(0));
+        }
+        Message::MoveFavorite(up) => {
+            if model.view != MainView::Favorites {
+                return None;
+            }
+            let station = selected_station(model)?;
+
+            if !model.favorites.shift(&model.stations[station].id, up) {
+                return None;
+            }
+            if let Err(err) = model.favorites.save() {
+                model.last_update = PlaybackUpdate::Error(err.to_string());
+            }
+
+            // Keep the moved station selected
+            return Some(Message::Navigation(if up {
+                KeyCode::Up
+            } else {
+                KeyCode::Down
+            }));
+        }
+        Message::QuickPlay(index) => {
+            let station = *model.favorites.stations(&model.stations).get(index)?;
+            play_station(model, station);
+        }
+        Message::OpenHistory => {
+            model.history_search.reset();
+            model.history_list_state.select(Some(0));
+            model.popup = Some(Popup::History);
+        }
+        Message::HistoryInput(event) => {
+            model.history_search.handle_event(&event);
+            model.history_list_state.select(Some(0));
+        }
+        Message::CopyHistoryTrack => {
+            let track = model
+                .history_list_state
+                .selected()
+                .and_then(|index| {
+                    model
+                        .history
+                        .search(model.history_search.value())
+                        .nth(index)
+                })
+                .map(HistoryEntry::track);
+
+            if let Some(cb) = &mut model.clipboard
+                && let Some(track) = track
+            {
+                let _ = cb.set_text(track);
+            }
+        }
+        Message::OpenSchedule => {
+            model.schedule_input = None;
+            model.schedule_error = None;
+            model.schedule_list_state.select(Some(0));
+            model.popup = Some(Popup::Schedule);
+        }
+        Message::EditSchedule(new) => {
+            let edited = model
+                .schedule_list_state
+                .selected()
+                .filter(|_| !new)
+                .and_then(|index| model.schedule.get(index).map(|entry| (index, entry)));
+
+            model.schedule_error = None;
+            model.schedule_input = match edited {
+                Some((index, entry)) => Some((entry.spec().into(), Some(index))),
+                None if model.current_station.is_some() => Some(("".into(), None)),
+                None => {
+                    model.schedule_error = Some("Play the station to schedule first".to_string());
+                    None
+                }
+            };
+        }
+        Message::ScheduleInput(event) => {
+            if let Some((input, _)) = &mut model.schedule_input {
+                input.handle_event(&event);
+            }
+        }
+        Message::DeleteSchedule => {
+            if let Some(index) = model.schedule_list_state.selected() {
+                model.schedule.remove(index);
+                if let Err(err) = model.schedule.save() {
+                    model.schedule_error = Some(err.to_string());
+                }
+            }
+        }
+        Message::ClosePopup if model.schedule_input.is_some() => model.schedule_input = None,
+        Message::ClosePopup if model.wishlist_input.is_some() => model.wishlist_input = None,
+        Message::ClosePopup => {
+            if model.popup == Some(Popup::Equalizer)
+                && let Ok(gains) = model.playback.equalizer()
+            {
+                let _ = equalizer::save(&gains);
+            }
+
+            model.popup = None;
+        }
         Message::CopyStationURL => {
             if let Some(cb) = &mut model.clipboard
                 && let Some(station) = model.current_station
@@ -292,20 +1068,354 @@ fn update(model: &mut AppModel, msg: Message) -> Option<Message> {
     None
 }
 
+/// Start and stop scheduled recordings
+fn tick_schedule(model: &mut AppModel, now: NaiveDateTime) -> Option<Message> {
+    if let Some(recording) = &mut model.scheduled_recording {
+        let station_changed = model
+            .current_station
+            .is_none_or(|index| model.stations[index].id != recording.entry.station_id);
+
+        if now >= recording.end() || station_changed {
+            // Done, or the user took over by switching stations
+            model.playback.stop_recording(false);
+            model.scheduled_recording = None;
+        } else if !recording.started {
+            // The recorder can only be attached once the stream is running
+            let result = model
+                .playback
+                .start_session_recording(&recording.path, &recording.entry.station_name);
+            match result {
+                Ok(()) => recording.started = true,
+                Err(PlaybackError::NotPlaying) => {}
+                Err(err) => {
+                    model.scheduled_recording = None;
+                    return Some(Message::PlaybackMsg(PlaybackUpdate::Error(err.to_string())));
+                }
+            }
+        }
+
+        return None;
+    }
+
+    let (entry, start) = model.schedule.active_at(now)?;
+    if model
+        .last_scheduled
+        .as_ref()
+        .is_some_and(|last| last.0 == *entry && last.1 == start)
+    {
+        return None;
+    }
+    model.last_scheduled = Some((entry.clone(), start));
+
+    let Some(station) = model
+        .stations
+        .iter()
+        .position(|station| station.id == entry.station_id)
+    else {
+        let msg = format!("Scheduled station {} not found", entry.station_name);
+        return Some(Message::PlaybackMsg(PlaybackUpdate::Error(msg)));
+    };
+
+    let recording = ActiveRecording {
+        entry: entry.clone(),
+        start,
+        path: recording_path(model, &entry.station_name, start),
+        started: false,
+    };
+
+    if model.current_station != Some(station) {
+        play_station(model, station);
+    } else {
+        // Stop splitting the stream into songs
+        model.playback.stop_recording(true);
+    }
+    model.scheduled_recording = Some(recording);
+
+    None
+}
+
+/// Where a session recording of some station that started at `start` is saved
+fn recording_path(model: &AppModel, station_name: &str, start: NaiveDateTime) -> PathBuf {
+    let name = format!("{station_name} {}", start.format("%Y-%m-%d %H-%M"));
+    model
+        .config
+        .saved_song_location
+        .join(format!("{}.mka", sanitize_filename(&name)))
+}
+
+/// Fade out and stop once the sleep timer runs out
+fn tick_sleep_timer(model: &mut AppModel, now: Instant) -> Option<Message> {
+    let timer = model.sleep_timer.as_ref()?;
+
+    match timer.state(now) {
+        TimerState::Waiting | TimerState::Fading => {
+            let _ = model.playback.set_volume(timer.volume(now));
+        }
+        TimerState::Expired => {
+            // Close the current recording properly, while there is still time
+            let _ = model.playback.set_volume(0.0);
+            model.playback.stop_recording(false);
+        }
+        TimerState::Done => {
+            model.sleep_timer = None;
+            match model.config.sleep_action {
+                SleepAction::Stop => {
+                    stop(model);
+                    let _ = model.playback.set_volume(1.0);
+                }
+                SleepAction::Quit => return Some(Message::Quit),
+            }
+        }
+    }
+
+    None
+}
+
 /// Play a station
 fn play_station(model: &mut AppModel, station_idx: usize) {
     stop(model);
+    model.current_station = Some(station_idx);
+
+    model.app_state.played(&model.stations[station_idx]);
+    let _ = model.app_state.save();
+
     model
         .playback
-        .set_source_uri(&model.stations[station_idx].url);
-    model.current_station = Some(station_idx);
-    model.playback.play();
+        .set_title_parser(title_parser(model, &model.stations[station_idx].id));
+
+    let result = model
+        .playback
+        .set_source_uri(&model.stations[station_idx].url)
+        .and_then(|_| model.playback.play());
+
+    if let Err(err) = result {
+        model.last_update = PlaybackUpdate::Error(err.to_string());
+        return;
+    }
+
+    if model.config.now_playing_interval > 0
+        && let Ok(provider) = now_playing::discover(&model.stations[station_idx].url)
+    {
+        model.playback.start_now_playing(
+            provider,
+            Duration::from_secs(model.config.now_playing_interval),
+        );
+    }
+}
+
+/// Record `station` in the background, next to the one that is played
+fn start_background_recording(model: &mut AppModel, station: Station) {
+    let parser = title_parser(model, &station.id);
+    let recorder = BackgroundRecorder::start(
+        station,
+        &model.config.playback_options(),
+        parser,
+        QUEUE_SIZE,
+        &model.config.temp_song_location,
+    );
+
+    match recorder {
+        Ok(recorder) => model.background.push(recorder),
+        Err(err) => model.last_update = PlaybackUpdate::Error(err.to_string()),
+    }
+}
+
+/// Stop a background recorder, saving the songs it finished first.
+/// It keeps running if they can't be saved, so they aren't lost
+fn stop_background_recording(model: &mut AppModel, index: usize) {
+    let recorder = &mut model.background[index];
+
+    match recorder.save_songs(&model.config.saved_song_location) {
+        Ok(_) => {
+            model.background.remove(index);
+        }
+        Err(err) => {
+            let err = format!("Could not save songs: {err}");
+            recorder.error = Some(err.clone());
+            model.last_update = PlaybackUpdate::Error(err);
+        }
+    }
+}
+
+/// Start watching the titles of the stations on the wishlist
+fn watch_wishlist_stations(model: &mut AppModel) {
+    let stations: Vec<Station> = model
+        .wishlist
+        .stations()
+        .filter_map(|watched| model.stations.iter().find(|s| s.id == watched.id))
+        .cloned()
+        .collect();
+
+    for station in stations {
+        let parser = title_parser(model, &station.id);
+        if let Some(monitor) = &mut model.monitor {
+            monitor.watch(&station, parser);
+        }
+    }
+}
+
+/// Let the user know that a wished song is on air, besides showing it
+fn notify(config: &Config, alert: &WishlistAlert) {
+    if config.wishlist_bell {
+        print!("\x07");
+        let _ = io::stdout().flush();
+    }
+
+    if config.wishlist_notify {
+        // Not having a notification daemon is fine
+        let _ = Command::new("notify-send")
+            .arg(format!("On {} now", alert.station_name))
+            .arg(alert.tags.to_string())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+    }
+}
+
+/// Indices of the stations the main area lists, matching the search
+pub fn shown_stations(model: &AppModel) -> Vec<usize> {
+    match model.view {
+        MainView::Stations => model
+            .stations
+            .search(model.stations_search.value())
+            .collect(),
+        MainView::Favorites => search_in(model, model.favorites.stations(&model.stations)),
+        MainView::Recent => search_in(model, model.app_state.recent_stations(&model.stations)),
+    }
+}
+
+/// The stations of `indices` the search matches, in their order.
+/// They are matched the same way as in the Stations view
+fn search_in(model: &AppModel, indices: Vec<usize>) -> Vec<usize> {
+    // Sorted, as the indices are searched in order
+    let matches: Vec<usize> = model
+        .stations
+        .search(model.stations_search.value())
+        .collect();
+
+    indices
+        .into_iter()
+        .filter(|index| matches.binary_search(index).is_ok())
+        .collect()
+}
+
+/// Index of the station that is selected in the stations table
+fn selected_station(model: &AppModel) -> Option<usize> {
+    let index = model.stations_table_state.selected()?;
+    shown_stations(model)
+        .into_iter()
+        .skip(model.table_virtual_offset)
+        .nth(index)
+}
+
+/// Bring back the search, selection and focus of the last run
+fn restore_ui_state(model: &mut AppModel) {
+    let state = &model.app_state;
+
+    model.stations_search = state.search.as_str().into();
+    model.view = state.view;
+    model.table_virtual_offset = state.table_offset;
+    model.last_selected_station = state.selected_station;
+    model
+        .stations_table_state
+        .select(Some(state.selected_station));
+
+    model.focus = state.focus;
+    if model.focus == FocusRegion::Queue {
+        model.queue_list_state.select(Some(0));
+    }
+}
+
+/// Keep the search, selection and focus for the next run
+fn save_ui_state(model: &mut AppModel) {
+    let state = &mut model.app_state;
+
+    state.search = model.stations_search.value().to_string();
+    state.view = model.view;
+    state.table_offset = model.table_virtual_offset;
+    state.selected_station = model.last_selected_station;
+    state.focus = model.focus;
+
+    let _ = state.save();
+}
+
+/// How the titles of a station are split up
+fn title_parser(model: &AppModel, station_id: &str) -> TitleParser {
+    TitleParser {
+        patterns: model.title_rules.split_patterns(station_id),
+        separators: model.config.title_separators.clone(),
+        strip_junk: model.config.strip_title_junk,
+        encoding: model.title_rules.encoding(station_id),
+    }
 }
 
 /// Stop playback
 fn stop(model: &mut AppModel) {
-    model.playback.stop_recording(true);
+    end_recording(model);
+    model.segment = None;
+    model.title_changed = false;
+    model.now_playing = None;
+    if let Some(scrobbler) = &mut model.scrobbler {
+        let _ = scrobbler.new_track(None);
+    }
     model.playback.stop();
+    model.stream_info = None;
+    model.level = None;
+    model.spectrum = None;
+}
+
+/// Stop the current recording. A session recording is finalized,
+/// while a song that is still being split off is incomplete and discarded
+fn end_recording(model: &mut AppModel) {
+    let discard = !model.playback.is_session_recording();
+    model.playback.stop_recording(discard);
+}
+
+fn handle_popup_navigation(model: &mut AppModel, popup: Popup, key: KeyCode) {
+    match popup {
+        Popup::OutputPicker => match key {
+            KeyCode::Up => model.outputs_list_state.select_previous(),
+            KeyCode::Down => model.outputs_list_state.select_next(),
+            _ => {}
+        },
+        Popup::SleepTimer => match key {
+            KeyCode::Up => model.sleep_timer_list_state.select_previous(),
+            KeyCode::Down => model.sleep_timer_list_state.select_next(),
+            _ => {}
+        },
+        Popup::Schedule => match key {
+            KeyCode::Up => model.schedule_list_state.select_previous(),
+            KeyCode::Down => model.schedule_list_state.select_next(),
+            _ => {}
+        },
+        Popup::History => match key {
+            KeyCode::Up => model.history_list_state.select_previous(),
+            KeyCode::Down => model.history_list_state.select_next(),
+            _ => {}
+        },
+        Popup::Wishlist => match key {
+            KeyCode::Up => model.wishlist_list_state.select_previous(),
+            KeyCode::Down => model.wishlist_list_state.select_next(),
+            _ => {}
+        },
+        Popup::BackgroundRecorders => match key {
+            KeyCode::Up => model.background_list_state.select_previous(),
+            KeyCode::Down => model.background_list_state.select_next(),
+            _ => {}
+        },
+        Popup::Equalizer => match key {
+            KeyCode::Left => model.equalizer_band = model.equalizer_band.saturating_sub(1),
+            KeyCode::Right => model.equalizer_band = (model.equalizer_band + 1).min(eq::BANDS - 1),
+            KeyCode::Up | KeyCode::Down => {
+                let step = if key == KeyCode::Up { 1.0 } else { -1.0 };
+                if let Ok(gains) = model.playback.equalizer() {
+                    let gain = gains[model.equalizer_band] + step;
+                    let _ = model.playback.set_band_gain(model.equalizer_band, gain);
+                }
+            }
+            _ => {}
+        },
+    }
 }
 
 fn handle_navigation(model: &mut AppModel, key: KeyCode) -> Option<FocusRegion> {
@@ -376,11 +1486,25 @@ fn handle_event(model: &AppModel) -> Result<Option<Message>, Box<dyn Error>> {
             |_| Ok(Some(Message::LoadCache)),
             |new_percentage| Ok(Some(Message::LoadingPercentage(new_percentage))),
         );
-    } else if let Ok(msg) = model.playback_receiver.try_recv() {
-        return Ok(Some(Message::PlaybackMsg(msg)));
+    } else if model.last_tick.elapsed() >= TICK_INTERVAL {
+        return Ok(Some(Message::Tick));
+    } else if let Ok(event) = model.playback_events.try_recv() {
+        return Ok(Some(Message::PlaybackMsg(event.update)));
+    } else if let Some((station_id, update)) = model.background.iter().find_map(|recorder| {
+        recorder
+            .try_recv()
+            .map(|update| (recorder.station.id.clone(), update))
+    }) {
+        return Ok(Some(Message::BackgroundMsg(station_id, update)));
+    } else if let Some(event) = model
+        .monitor
+        .as_ref()
+        .and_then(|monitor| monitor.try_recv().ok())
+    {
+        return Ok(Some(Message::MonitorMsg(event)));
     }
 
-    if event::poll(Duration::from_millis(250))?
+    if event::poll(TICK_INTERVAL)?
         && let event::Event::Key(key) = event::read()?
         && key.kind == event::KeyEventKind::Press
     {
@@ -396,10 +1520,101 @@ fn handle_event(model: &AppModel) -> Result<Option<Message>, Box<dyn Error>> {
 }
 
 const fn handle_key(model: &AppModel, key: event::KeyEvent) -> Option<Message> {
+    // Typing in a schedule
+    if matches!(model.popup, Some(Popup::Schedule)) && model.schedule_input.is_some() {
+        return match key.code {
+            KeyCode::Enter => Some(Message::Selection),
+            KeyCode::Esc => Some(Message::ClosePopup),
+            _ => Some(Message::ScheduleInput(Event::Key(key))),
+        };
+    }
+
+    // Searching the history
+    if matches!(model.popup, Some(Popup::History)) {
+        return match key.code {
+            KeyCode::Up | KeyCode::Down => Some(Message::Navigation(key.code)),
+            KeyCode::Enter => Some(Message::Selection),
+            KeyCode::Tab => Some(Message::CopyHistoryTrack),
+            KeyCode::Esc => Some(Message::ClosePopup),
+            _ => Some(Message::HistoryInput(Event::Key(key))),
+        };
+    }
+
+    // Typing in a wishlist pattern
+    if matches!(model.popup, Some(Popup::Wishlist)) && model.wishlist_input.is_some() {
+        return match key.code {
+            KeyCode::Enter => Some(Message::Selection),
+            KeyCode::Esc => Some(Message::ClosePopup),
+            _ => Some(Message::WishlistInput(Event::Key(key))),
+        };
+    }
+
+    if model.popup.is_some() {
+        return match key.code {
+            KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right => {
+                Some(Message::Navigation(key.code))
+            }
+            KeyCode::Enter => Some(Message::Selection),
+            KeyCode::Char('p') if matches!(model.popup, Some(Popup::Equalizer)) => {
+                Some(Message::NextEqualizerPreset)
+            }
+            KeyCode::Char('0'..='9') | KeyCode::Backspace
+                if matches!(model.popup, Some(Popup::SleepTimer)) =>
+            {
+                Some(Message::SleepTimerInput(Event::Key(key)))
+            }
+            KeyCode::Char('a') if matches!(model.popup, Some(Popup::Schedule)) => {
+                Some(Message::EditSchedule(true))
+            }
+            KeyCode::Char('d') if matches!(model.popup, Some(Popup::Schedule)) => {
+                Some(Message::DeleteSchedule)
+            }
+            KeyCode::Char('a') if matches!(model.popup, Some(Popup::Wishlist)) => {
+                Some(Message::EditWishlist)
+            }
+            KeyCode::Char('d') if matches!(model.popup, Some(Popup::Wishlist)) => {
+                Some(Message::DeleteWishlistPattern)
+            }
+            KeyCode::Char('d') if matches!(model.popup, Some(Popup::BackgroundRecorders)) => {
+                Some(Message::StopBackgroundRecording)
+            }
+            KeyCode::Esc | KeyCode::Char('q') => Some(Message::ClosePopup),
+            _ => None,
+        };
+    }
+
     match key.code {
         KeyCode::Char('q') => Some(Message::Quit),
         KeyCode::Char('y') => Some(Message::CopyStationURL),
         KeyCode::Char('s') => Some(Message::StopPlayback),
+        KeyCode::Char('o') => Some(Message::OpenOutputPicker),
+        KeyCode::Char('e') => Some(Message::OpenEqualizer),
+        KeyCode::Char('t') => Some(Message::OpenSleepTimer),
+        KeyCode::Char('T') => Some(Message::CancelSleepTimer),
+        KeyCode::Char('r') => Some(Message::OpenSchedule),
+        KeyCode::Char('c') => Some(Message::ToggleSessionRecording),
+        KeyCode::Char('b') => Some(Message::ToggleBackgroundRecording),
+        KeyCode::Char('B') => Some(Message::OpenBackgroundRecorders),
+        KeyCode::Char('w') => Some(Message::ToggleWatchStation),
+        KeyCode::Char('W') => Some(Message::OpenWishlist),
+        KeyCode::Char('h') => Some(Message::OpenHistory),
+        KeyCode::Char('f') => Some(Message::ToggleFavorite),
+        KeyCode::Char('F') => Some(Message::SwitchView(MainView::Favorites)),
+        KeyCode::Char('R') => Some(Message::SwitchView(MainView::Recent)),
+        KeyCode::Up if key.modifiers.contains(KeyModifiers::SHIFT) => {
+            Some(Message::MoveFavorite(true))
+        }
+        KeyCode::Down if key.modifiers.contains(KeyModifiers::SHIFT) => {
+            Some(Message::MoveFavorite(false))
+        }
+        // 1 to 9, then 0 for the tenth
+        KeyCode::Char(digit @ '0'..='9') => match digit.to_digit(10) {
+            Some(0) => Some(Message::QuickPlay(9)),
+            Some(n) => Some(Message::QuickPlay(n as usize - 1)),
+            None => None,
+        },
+        KeyCode::Char('n') => Some(Message::TuneIn),
+        KeyCode::Char('N') => Some(Message::DismissAlert),
         KeyCode::Char('/') => Some(Message::ToggleSearch(!model.search_toggled)),
         KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right => {
             Some(Message::Navigation(key.code))
@@ -0,0 +1,344 @@
+use std::{
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::get_data_dir;
+
+const SCHEDULE_FILE: &str = "schedule.json";
+
+/// Longest a single scheduled recording can be (in minutes)
+const MAX_DURATION: u32 = 24 * 60;
+
+/// On which days a scheduled recording happens
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recurrence {
+    Once(NaiveDate),
+    Daily,
+    Weekdays,
+    Weekends,
+    Weekly(Weekday),
+}
+
+impl Recurrence {
+    fn matches(self, date: NaiveDate) -> bool {
+        let weekend = matches!(date.weekday(), Weekday::Sat | Weekday::Sun);
+
+        match self {
+            Self::Once(day) => day == date,
+            Self::Daily => true,
+            Self::Weekdays => !weekend,
+            Self::Weekends => weekend,
+            Self::Weekly(weekday) => date.weekday() == weekday,
+        }
+    }
+}
+
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Once(date) => write!(f, "{date}"),
+            Self::Daily => write!(f, "daily"),
+            Self::Weekdays => write!(f, "weekdays"),
+            Self::Weekends => write!(f, "weekends"),
+            Self::Weekly(weekday) => write!(f, "{}", weekday.to_string().to_lowercase()),
+        }
+    }
+}
+
+/// A recording of some station that happens at a fixed time
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleEntry {
+    pub station_id: String,
+    /// Only used for display, the station is looked up by id
+    pub station_name: String,
+    pub start: NaiveTime,
+    pub duration_minutes: u32,
+    pub recurrence: Recurrence,
+}
+
+impl ScheduleEntry {
+    /// Parses a schedule like `20:00 120 weekdays` (start, duration in minutes, recurrence).
+    /// The recurrence is one of `daily`, `weekdays`, `weekends`, a weekday (`mon`, `friday`...)
+    /// or a date (`2026-12-31`) for a one time recording
+    pub fn parse(spec: &str, station_id: &str, station_name: &str) -> Result<Self, String> {
+        let mut parts = spec.split_whitespace();
+        let (Some(start), Some(duration), Some(recurrence), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err("Expected <HH:MM> <minutes> <recurrence>".to_string());
+        };
+
+        let start = NaiveTime::parse_from_str(start, "%H:%M")
+            .map_err(|_| format!("Invalid start time: {start}"))?;
+
+        let duration_minutes = duration
+            .parse()
+            .ok()
+            .filter(|minutes| (1..=MAX_DURATION).contains(minutes))
+            .ok_or_else(|| format!("Invalid duration: {duration}"))?;
+
+        let recurrence = match recurrence.to_lowercase().as_str() {
+            "daily" => Recurrence::Daily,
+            "weekdays" => Recurrence::Weekdays,
+            "weekends" => Recurrence::Weekends,
+            other => other
+                .parse::<Weekday>()
+                .map(Recurrence::Weekly)
+                .or_else(|_| other.parse::<NaiveDate>().map(Recurrence::Once))
+                .map_err(|_| format!("Invalid recurrence: {recurrence}"))?,
+        };
+
+        Ok(Self {
+            station_id: station_id.to_string(),
+            station_name: station_name.to_string(),
+            start,
+            duration_minutes,
+            recurrence,
+        })
+    }
+
+    /// The schedule in the format `parse` accepts
+    pub fn spec(&self) -> String {
+        format!(
+            "{} {} {}",
+            self.start.format("%H:%M"),
+            self.duration_minutes,
+            self.recurrence
+        )
+    }
+
+    pub fn duration(&self) -> TimeDelta {
+        TimeDelta::minutes(self.duration_minutes.into())
+    }
+
+    /// Start times of all occurrences on the days `from..=to`
+    fn occurrences(&self, from: NaiveDate, to: NaiveDate) -> impl Iterator<Item = NaiveDateTime> {
+        from.iter_days()
+            .take_while(move |day| *day <= to)
+            .filter(|day| self.recurrence.matches(*day))
+            .map(|day| day.and_time(self.start))
+    }
+
+    /// Start of the occurrence that is running at `now`, if there is one
+    pub fn active_at(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        // Recordings can run past midnight, so check yesterday as well
+        let yesterday = now.date() - Days::new(1);
+        self.occurrences(yesterday, now.date())
+            .find(|start| *start <= now && now < *start + self.duration())
+    }
+
+    /// Start of the next occurrence after `now`
+    pub fn next_after(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.occurrences(now.date(), now.date() + Days::new(7))
+            .find(|start| *start > now)
+            .or(match self.recurrence {
+                Recurrence::Once(date) if date > now.date() => Some(date.and_time(self.start)),
+                _ => None,
+            })
+    }
+
+    /// Checks if any occurrences of the two entries overlap
+    pub fn conflicts_with(&self, other: &Self, today: NaiveDate) -> bool {
+        // A week (plus a day for recordings running past midnight) covers every
+        // combination of recurrences, one time recordings only need their own date
+        let (from, to) = match (self.recurrence, other.recurrence) {
+            (Recurrence::Once(date), _) | (_, Recurrence::Once(date)) => {
+                (date - Days::new(1), date + Days::new(1))
+            }
+            _ => (today, today + Days::new(8)),
+        };
+
+        self.occurrences(from, to).any(|start| {
+            let end = start + self.duration();
+            other
+                .occurrences(from, to)
+                .any(|other_start| other_start < end && start < other_start + other.duration())
+        })
+    }
+}
+
+/// A scheduled recording that is currently running
+#[derive(Debug)]
+pub struct ActiveRecording {
+    pub entry: ScheduleEntry,
+    /// Start of the occurrence that is being recorded
+    pub start: NaiveDateTime,
+    /// Where the whole recording is written to
+    pub path: PathBuf,
+    /// Wether the recorder is attached yet, this has to wait until the stream is playing
+    pub started: bool,
+}
+
+impl ActiveRecording {
+    pub fn end(&self) -> NaiveDateTime {
+        self.start + self.entry.duration()
+    }
+}
+
+/// All scheduled recordings, stored in the data directory
+#[derive(Debug, Default)]
+pub struct Schedule {
+    entries: Vec<ScheduleEntry>,
+}
+
+impl Schedule {
+    pub fn path() -> PathBuf {
+        get_data_dir().join(SCHEDULE_FILE)
+    }
+
+    /// Read the stored schedule, an empty one if there is none
+    pub fn load() -> io::Result<Self> {
+        Self::load_from(&Self::path())
+    }
+
+    fn load_from(path: &Path) -> io::Result<Self> {
+        let entries = match fs::read(path) {
+            Ok(data) => serde_json::from_slice(&data).map_err(io::Error::other)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => vec![],
+            Err(err) => return Err(err),
+        };
+
+        Ok(Self { entries })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let data = serde_json::to_vec_pretty(&self.entries).map_err(io::Error::other)?;
+        fs::write(Self::path(), data)
+    }
+
+    /// Add an entry, or replace the one at `replace`.
+    /// Fails with the conflicting entry if the new one overlaps with another
+    pub fn insert(
+        &mut self,
+        entry: ScheduleEntry,
+        replace: Option<usize>,
+        today: NaiveDate,
+    ) -> Result<(), ScheduleEntry> {
+        if let Some(conflict) = self
+            .entries
+            .iter()
+            .enumerate()
+            .find(|(i, other)| Some(*i) != replace && entry.conflicts_with(other, today))
+        {
+            return Err(conflict.1.clone());
+        }
+
+        match replace.and_then(|i| self.entries.get_mut(i)) {
+            Some(old) => *old = entry,
+            None => self.entries.push(entry),
+        }
+
+        Ok(())
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.entries.len() {
+            self.entries.remove(index);
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&ScheduleEntry> {
+        self.entries.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ScheduleEntry> {
+        self.entries.iter()
+    }
+
+    /// The entry that should be recording at `now`, and the start of its occurrence
+    pub fn active_at(&self, now: NaiveDateTime) -> Option<(&ScheduleEntry, NaiveDateTime)> {
+        self.entries
+            .iter()
+            .find_map(|entry| entry.active_at(now).map(|start| (entry, start)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    fn parse(spec: &str) -> Result<ScheduleEntry, String> {
+        ScheduleEntry::parse(spec, "id", "Station")
+    }
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        date.parse::<NaiveDate>()
+            .unwrap()
+            .and_time(time.parse().unwrap())
+    }
+
+    #[test]
+    fn parse_spec() {
+        let entry = parse("20:00 120 weekdays").unwrap();
+        assert_eq!(entry.start, NaiveTime::from_hms_opt(20, 0, 0).unwrap());
+        assert_eq!(entry.duration_minutes, 120);
+        assert_eq!(entry.recurrence, Recurrence::Weekdays);
+        assert_eq!(entry.spec(), "20:00 120 weekdays");
+
+        let entry = parse("08:30 60 fri").unwrap();
+        assert_eq!(entry.recurrence, Recurrence::Weekly(Weekday::Fri));
+
+        assert!(parse("8pm 60 daily").is_err());
+        assert!(parse("20:00 0 daily").is_err());
+        assert!(parse("20:00 60").is_err());
+    }
+
+    #[test]
+    fn active_occurrence() {
+        let entry = parse("23:00 120 weekdays").unwrap();
+
+        // 2026-10-16 is a friday
+        assert_eq!(
+            entry.active_at(at("2026-10-16", "23:30:00")),
+            Some(at("2026-10-16", "23:00:00"))
+        );
+        // Running past midnight into saturday
+        assert_eq!(
+            entry.active_at(at("2026-10-17", "00:30:00")),
+            Some(at("2026-10-16", "23:00:00"))
+        );
+        assert_eq!(entry.active_at(at("2026-10-17", "23:30:00")), None);
+        assert_eq!(
+            entry.next_after(at("2026-10-17", "12:00:00")),
+            Some(at("2026-10-19", "23:00:00"))
+        );
+    }
+
+    #[test]
+    fn conflicts() {
+        let today = "2026-10-18".parse().unwrap();
+        let weekdays = parse("20:00 120 weekdays").unwrap();
+        let monday = parse("21:00 30 mon").unwrap();
+        let saturday = parse("21:00 30 sat").unwrap();
+        let once = parse("19:00 61 2026-10-20").unwrap();
+
+        assert!(weekdays.conflicts_with(&monday, today));
+        assert!(!weekdays.conflicts_with(&saturday, today));
+        assert!(weekdays.conflicts_with(&once, today));
+
+        let mut schedule = Schedule::default();
+        schedule.insert(weekdays.clone(), None, today).unwrap();
+        assert!(schedule.insert(saturday, None, today).is_ok());
+        assert_eq!(schedule.insert(monday.clone(), None, today), Err(weekdays));
+        // Replacing an entry doesn't conflict with itself
+        assert!(schedule.insert(monday, Some(0), today).is_ok());
+    }
+
+    #[test]
+    fn corrupt_file_is_kept() {
+        let path = env::temp_dir().join(format!("amfm-schedule-{}.json", process::id()));
+        fs::write(&path, "[{\"start\": ").unwrap();
+        assert!(Schedule::load_from(&path).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[{\"start\": ");
+
+        fs::remove_file(&path).unwrap();
+        assert!(Schedule::load_from(&path).unwrap().entries.is_empty());
+    }
+}
@@ -1,14 +1,20 @@
-use antenna::stations::StationList;
+use std::time::Instant;
+
+use chrono::Local;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, List, Paragraph, Wrap},
 };
+use tui_input::Input;
 
 use crate::{
-    AppModel, FocusRegion,
+    AppModel, FocusRegion, MainView, Popup, shown_stations,
     utils::center_vertical,
     widgets::{
-        radio_info::RadioInfo, shortcuts_display::ShortcutsDisplay, stations_table::StationsTable,
+        background_list::BackgroundList, equalizer::EqualizerPanel, history_list::HistoryList,
+        output_picker::OutputPicker, radio_info::RadioInfo, schedule_list::ScheduleList,
+        shortcuts_display::ShortcutsDisplay, sleep_timer_picker::SleepTimerPicker,
+        stations_table::StationsTable, wishlist_panel::WishlistPanel,
     },
 };
 
@@ -21,10 +27,9 @@ impl Widget for PlayScreen<'_> {
     where
         Self: Sized,
     {
-        let stations_iter = self
-            .model
-            .stations
-            .search(self.model.stations_search.value())
+        let shown = shown_stations(self.model);
+        let stations_iter = shown
+            .into_iter()
             .map(|i| &self.model.stations[i])
             .skip(self.model.table_virtual_offset)
             .take(self.model.table_size.into());
@@ -49,7 +54,12 @@ impl Widget for PlayScreen<'_> {
         .areas(main_area);
 
         // Blocks
-        let mut main = Block::new().borders(Borders::all()).title_top("Stations");
+        let title = match self.model.view {
+            MainView::Stations => "Stations",
+            MainView::Favorites => "Favorites",
+            MainView::Recent => "Recently played",
+        };
+        let mut main = Block::new().borders(Borders::all()).title_top(title);
         if self.model.focus != FocusRegion::MainArea {
             main = main.border_style(Style::new().dim());
         }
@@ -64,13 +74,14 @@ impl Widget for PlayScreen<'_> {
         }
 
         let [radio_info_area, queue_area] =
-            Layout::vertical([Constraint::Max(10), Constraint::Fill(1)]).areas(sidebar_area);
+            Layout::vertical([Constraint::Max(16), Constraint::Fill(1)]).areas(sidebar_area);
 
         // Main Area
         let table = StationsTable {
             stations: Box::new(stations_iter),
             state: &mut self.model.stations_table_state,
             focused: self.model.focus == FocusRegion::MainArea,
+            favorites: &self.model.favorites,
         };
 
         table.render(main.inner(main_area), buf);
@@ -82,9 +93,18 @@ impl Widget for PlayScreen<'_> {
         if let Some(station) = self.model.current_station {
             let radio_info = RadioInfo {
                 name: &self.model.stations[station].name,
-                current_song: self.model.queue.last(),
+                current_song: self.model.now_playing.as_deref(),
                 is_recording: self.model.playback.is_recording(),
+                is_session_recording: self.model.playback.is_session_recording(),
                 last_update: &self.model.last_update,
+                stream_info: self.model.stream_info.as_ref(),
+                level: self.model.level.as_ref(),
+                spectrum: self.model.spectrum.as_deref(),
+                sleep_remaining: self
+                    .model
+                    .sleep_timer
+                    .as_ref()
+                    .map(|timer| timer.remaining(Instant::now())),
             };
 
             radio_info.render(radio_info_area_inner, buf);
@@ -109,7 +129,9 @@ impl Widget for PlayScreen<'_> {
         let queue_list = List::new(self.model.queue.iter().enumerate().map(|(i, item)| {
             let title = item.to_string();
 
-            if i == 0 {
+            if let Some(label) = item.kind.label() {
+                Span::styled(format!("[{label}] {title}"), Style::default().dark_gray())
+            } else if i == 0 {
                 Span::styled(title, Style::default().dim().italic())
             } else {
                 Span::raw(title)
@@ -131,7 +153,98 @@ impl Widget for PlayScreen<'_> {
         text_input.render(search_area, buf);
 
         // Shortcuts
-        let shortcuts = ShortcutsDisplay {};
-        shortcuts.render(shortcuts_area, buf);
+        // A wished song on air takes the place of the shortcuts
+        if let Some(alert) = &self.model.wishlist_alert {
+            Line::from(vec![
+                Span::raw(format!(" {} ", alert.station_name))
+                    .black()
+                    .on_light_green(),
+                Span::raw(format!(" {} ", alert.tags)).bold(),
+                Span::raw("[n] Tune in  [N] Dismiss").light_magenta(),
+            ])
+            .render(shortcuts_area, buf);
+        } else {
+            let shortcuts = ShortcutsDisplay {};
+            shortcuts.render(shortcuts_area, buf);
+        }
+
+        // Popups
+        match self.model.popup {
+            Some(Popup::OutputPicker) => {
+                let picker = OutputPicker {
+                    outputs: &self.model.outputs,
+                    current: &self.model.audio_sink,
+                    state: &mut self.model.outputs_list_state,
+                };
+
+                picker.render(full_area, buf);
+            }
+            Some(Popup::Equalizer) => {
+                if let Ok(gains) = self.model.playback.equalizer() {
+                    let panel = EqualizerPanel {
+                        gains: &gains,
+                        selected_band: self.model.equalizer_band,
+                    };
+
+                    panel.render(full_area, buf);
+                }
+            }
+            Some(Popup::SleepTimer) => {
+                let picker = SleepTimerPicker {
+                    custom_minutes: self.model.sleep_timer_input.value(),
+                    state: &mut self.model.sleep_timer_list_state,
+                };
+
+                picker.render(full_area, buf);
+            }
+            Some(Popup::Schedule) => {
+                let list = ScheduleList {
+                    schedule: &self.model.schedule,
+                    recording: self
+                        .model
+                        .scheduled_recording
+                        .as_ref()
+                        .map(|recording| &recording.entry),
+                    input: self
+                        .model
+                        .schedule_input
+                        .as_ref()
+                        .map(|(input, _)| input.value()),
+                    error: self.model.schedule_error.as_deref(),
+                    now: Local::now().naive_local(),
+                    state: &mut self.model.schedule_list_state,
+                };
+
+                list.render(full_area, buf);
+            }
+            Some(Popup::BackgroundRecorders) => {
+                let list = BackgroundList {
+                    recorders: &self.model.background,
+                    state: &mut self.model.background_list_state,
+                };
+
+                list.render(full_area, buf);
+            }
+            Some(Popup::History) => {
+                let list = HistoryList {
+                    history: &self.model.history,
+                    search: self.model.history_search.value(),
+                    state: &mut self.model.history_list_state,
+                };
+
+                list.render(full_area, buf);
+            }
+            Some(Popup::Wishlist) => {
+                let panel = WishlistPanel {
+                    wishlist: &self.model.wishlist,
+                    input: self.model.wishlist_input.as_ref().map(Input::value),
+                    error: self.model.wishlist_error.as_deref(),
+                    state: &mut self.model.wishlist_list_state,
+                };
+
+                panel.render(full_area, buf);
+            }
+            None => {}
+        }
     }
 }
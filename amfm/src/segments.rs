@@ -0,0 +1,219 @@
+use std::time::{Duration, Instant};
+
+use antenna::playback::{AudioLevel, TrackTags};
+use regex::Regex;
+
+/// Titles matching one of these are treated as ads, unless other patterns are configured
+pub const DEFAULT_AD_PATTERNS: [&str; 4] = [
+    r"\b(advert(isement)?s?|commercials?|ad ?break|werbung|publicit[ée])\b",
+    r"\bjingles?\b",
+    r"\bstation ?id\b",
+    r"^\s*$",
+];
+
+/// Segments which are silent for more than this share of their length count as silence
+const SILENT_SHARE: f64 = 0.8;
+
+/// What a title change started, as far as we can tell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SegmentKind {
+    #[default]
+    Music,
+    /// The title looks like an ad
+    Ad,
+    /// Too short to be a song, usually a jingle or a spot
+    Jingle,
+    /// Mostly silence
+    Silence,
+}
+
+impl SegmentKind {
+    /// Label shown in the queue for everything that is not music
+    pub const fn label(self) -> Option<&'static str> {
+        match self {
+            Self::Music => None,
+            Self::Ad => Some("Ad"),
+            Self::Jingle => Some("Jingle"),
+            Self::Silence => Some("Silence"),
+        }
+    }
+}
+
+/// Heuristics to tell songs apart from everything else stations play
+#[derive(Debug)]
+pub struct Detector {
+    ad_patterns: Vec<Regex>,
+    /// Audio below this level (dB) counts as silence
+    silence_threshold: f64,
+    /// Shorter segments are treated as jingles
+    min_song_length: Duration,
+}
+
+impl Detector {
+    /// The ad patterns are matched against the title and the artist
+    pub const fn new(
+        ad_patterns: Vec<Regex>,
+        silence_threshold: f64,
+        min_song_length: Duration,
+    ) -> Self {
+        Self {
+            ad_patterns,
+            silence_threshold,
+            min_song_length,
+        }
+    }
+
+    /// Checks the tags of a segment before anything of it was heard
+    pub fn classify_tags(&self, tags: &TrackTags) -> SegmentKind {
+        let text = match &tags.artist {
+            Some(artist) => format!("{artist} - {}", tags.title),
+            None => tags.title.clone(),
+        };
+
+        if self
+            .ad_patterns
+            .iter()
+            .any(|pattern| pattern.is_match(&tags.title) || pattern.is_match(&text))
+        {
+            SegmentKind::Ad
+        } else {
+            SegmentKind::Music
+        }
+    }
+
+    /// Checks a segment that has ended
+    pub fn classify_segment(&self, segment: &Segment, now: Instant) -> SegmentKind {
+        if segment.samples > 0
+            && f64::from(segment.silent) / f64::from(segment.samples) > SILENT_SHARE
+        {
+            SegmentKind::Silence
+        } else if !segment.partial && now.duration_since(segment.started) < self.min_song_length {
+            SegmentKind::Jingle
+        } else {
+            SegmentKind::Music
+        }
+    }
+}
+
+/// Level statistics of the segment that is being recorded
+#[derive(Debug)]
+pub struct Segment {
+    started: Instant,
+    /// The recording started in the middle of the segment, so its length says nothing
+    partial: bool,
+    /// Amount of level updates
+    samples: u32,
+    /// Amount of level updates below the silence threshold
+    silent: u32,
+}
+
+impl Segment {
+    pub const fn new(started: Instant, partial: bool) -> Self {
+        Self {
+            started,
+            partial,
+            samples: 0,
+            silent: 0,
+        }
+    }
+
+    /// Add a level update of the playback. `volume` is the playback volume the level was
+    /// measured at, as the level is measured after the volume is applied
+    pub fn add_level(&mut self, detector: &Detector, level: &AudioLevel, volume: f64) {
+        // Muted or faded out, nothing to learn about the stream
+        if volume < 0.01 {
+            return;
+        }
+
+        let gain = 20.0 * volume.log10();
+        let loudest = level.rms.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        self.samples += 1;
+        if loudest - gain < detector.silence_threshold {
+            self.silent += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::case_insensitive;
+
+    fn detector() -> Detector {
+        let patterns = DEFAULT_AD_PATTERNS.map(|pattern| case_insensitive(pattern).unwrap());
+        Detector::new(patterns.to_vec(), -50.0, Duration::from_secs(60))
+    }
+
+    #[test]
+    fn ad_titles() {
+        let detector = detector();
+
+        assert_eq!(
+            detector.classify_tags(&TrackTags::mock("Advertisement", None)),
+            SegmentKind::Ad
+        );
+        assert_eq!(
+            detector.classify_tags(&TrackTags::mock("Radio X Station ID", None)),
+            SegmentKind::Ad
+        );
+        assert_eq!(
+            detector.classify_tags(&TrackTags::mock("", None)),
+            SegmentKind::Ad
+        );
+        assert_eq!(
+            detector.classify_tags(&TrackTags::mock("Bad Blood", Some("Taylor Swift"))),
+            SegmentKind::Music
+        );
+    }
+
+    #[test]
+    fn segments() {
+        let detector = detector();
+        let start = Instant::now();
+        let loud = AudioLevel {
+            rms: vec![-20.0, -70.0],
+            peak: vec![-10.0, -60.0],
+        };
+        let quiet = AudioLevel {
+            rms: vec![-70.0, -70.0],
+            peak: vec![-60.0, -60.0],
+        };
+
+        let mut segment = Segment::new(start, false);
+        segment.add_level(&detector, &loud, 1.0);
+        assert_eq!(
+            detector.classify_segment(&segment, start + Duration::from_secs(10)),
+            SegmentKind::Jingle
+        );
+        assert_eq!(
+            detector.classify_segment(&segment, start + Duration::from_secs(180)),
+            SegmentKind::Music
+        );
+        // Tuned in at the end of a song
+        assert_eq!(
+            detector.classify_segment(&Segment::new(start, true), start + Duration::from_secs(10)),
+            SegmentKind::Music
+        );
+
+        // Quiet only because of the volume
+        let mut segment = Segment::new(start, false);
+        for _ in 0..10 {
+            segment.add_level(&detector, &quiet, 0.01);
+        }
+        segment.add_level(&detector, &quiet, 0.0);
+        assert_eq!(
+            detector.classify_segment(&segment, start + Duration::from_secs(180)),
+            SegmentKind::Music
+        );
+
+        segment.add_level(&detector, &quiet, 1.0);
+        for _ in 0..50 {
+            segment.add_level(&detector, &quiet, 1.0);
+        }
+        assert_eq!(
+            detector.classify_segment(&segment, start + Duration::from_secs(180)),
+            SegmentKind::Silence
+        );
+    }
+}
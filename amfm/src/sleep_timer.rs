@@ -0,0 +1,98 @@
+use std::time::{Duration, Instant};
+
+/// Durations (in minutes) offered in the sleep timer popup
+pub const PRESETS: [u64; 6] = [15, 30, 45, 60, 90, 120];
+
+/// How long to wait for recordings to be finalized before stopping
+const FINALIZE_GRACE: Duration = Duration::from_secs(3);
+
+/// What the sleep timer is currently doing, see `SleepTimer::state`
+#[derive(Debug, PartialEq, Eq)]
+pub enum TimerState {
+    /// Playing at full volume
+    Waiting,
+    /// Fading out, the volume should be lowered
+    Fading,
+    /// The time is up: recordings should be finalized
+    Expired,
+    /// Recordings had time to finalize, playback can be stopped
+    Done,
+}
+
+/// Fades out the playback and stops it after some time
+#[derive(Debug)]
+pub struct SleepTimer {
+    deadline: Instant,
+    fade: Duration,
+}
+
+impl SleepTimer {
+    pub fn new(duration: Duration, fade: Duration, now: Instant) -> Self {
+        Self {
+            deadline: now + duration,
+            fade: fade.min(duration),
+        }
+    }
+
+    /// Time left until the playback stops
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.deadline.saturating_duration_since(now)
+    }
+
+    /// Factor to apply to the volume: 1.0 until the fade window starts,
+    /// then lowering linearly down to 0.0 at the deadline
+    pub fn volume(&self, now: Instant) -> f64 {
+        let remaining = self.remaining(now);
+        if self.fade.is_zero() || remaining >= self.fade {
+            return if remaining.is_zero() { 0.0 } else { 1.0 };
+        }
+
+        remaining.as_secs_f64() / self.fade.as_secs_f64()
+    }
+
+    pub fn state(&self, now: Instant) -> TimerState {
+        if now >= self.deadline + FINALIZE_GRACE {
+            TimerState::Done
+        } else if now >= self.deadline {
+            TimerState::Expired
+        } else if self.remaining(now) < self.fade {
+            TimerState::Fading
+        } else {
+            TimerState::Waiting
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fade_out() {
+        let now = Instant::now();
+        let timer = SleepTimer::new(Duration::from_secs(60), Duration::from_secs(20), now);
+
+        assert_eq!(timer.volume(now), 1.0);
+        assert_eq!(timer.volume(now + Duration::from_secs(40)), 1.0);
+        assert_eq!(timer.volume(now + Duration::from_secs(50)), 0.5);
+        assert_eq!(timer.volume(now + Duration::from_secs(60)), 0.0);
+        assert_eq!(timer.volume(now + Duration::from_secs(90)), 0.0);
+    }
+
+    #[test]
+    fn states() {
+        let now = Instant::now();
+        let timer = SleepTimer::new(Duration::from_secs(60), Duration::from_secs(20), now);
+
+        assert_eq!(timer.state(now), TimerState::Waiting);
+        assert_eq!(
+            timer.state(now + Duration::from_secs(45)),
+            TimerState::Fading
+        );
+        assert_eq!(
+            timer.state(now + Duration::from_secs(60)),
+            TimerState::Expired
+        );
+        assert_eq!(timer.state(now + Duration::from_secs(70)), TimerState::Done);
+    }
+}
@@ -8,10 +8,14 @@ use std::{
 
 use antenna::playback::TrackTags;
 
+use crate::segments::SegmentKind;
+
 #[derive(Debug, PartialEq)]
 pub struct Song {
     pub tags: Arc<TrackTags>,
     pub path: PathBuf,
+    /// Only music is kept on disk, anything else is just listed
+    pub kind: SegmentKind,
 }
 
 impl Song {
@@ -19,6 +23,7 @@ impl Song {
         let mut song = Self {
             tags,
             path: PathBuf::new(),
+            kind: SegmentKind::Music,
         };
 
         song.path = dir
@@ -32,21 +37,15 @@ impl Song {
     fn mock(title: &str) -> Self {
         Self {
             path: PathBuf::from(title),
-            tags: Arc::new(TrackTags {
-                title: title.to_string(),
-                artist: None,
-            }),
+            kind: SegmentKind::Music,
+            tags: Arc::new(TrackTags::mock(title, None)),
         }
     }
 }
 
 impl Display for Song {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if let Some(artist) = &self.tags.artist {
-            write!(f, "{artist} - {}", self.tags.title)
-        } else {
-            write!(f, "{}", self.tags.title)
-        }
+        self.tags.fmt(f)
     }
 }
 
@@ -99,6 +98,18 @@ impl SongQueue {
         self.queue.front()
     }
 
+    /// Mark the newest song as something that isn't music and delete its recording
+    pub fn discard_last(&mut self, kind: SegmentKind) -> Result<(), io::Error> {
+        if let Some(song) = self.queue.front_mut() {
+            song.kind = kind;
+            if song.path.exists() {
+                fs::remove_file(&song.path)?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Remove some song from the queue by index
     /// NOTE: Does not delete the file!
     pub fn remove(&mut self, index: usize) {
@@ -0,0 +1,266 @@
+use std::{collections::HashMap, fs, io, path::PathBuf};
+
+use antenna::{
+    charset::{self, Encoding},
+    playback::TrackTags,
+};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::utils::{case_insensitive, get_data_dir};
+
+const RULES_FILE: &str = "title_rules.json";
+
+/// A case insensitive regex, matched against the title and `artist - title`
+#[derive(Deserialize, Debug)]
+#[serde(try_from = "String")]
+pub struct Pattern(Regex);
+
+impl TryFrom<String> for Pattern {
+    type Error = regex::Error;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        case_insensitive(&pattern).map(Self)
+    }
+}
+
+impl Pattern {
+    pub const fn regex(&self) -> &Regex {
+        &self.0
+    }
+
+    fn matches(&self, tags: &TrackTags) -> bool {
+        self.0.is_match(&tags.title) || self.0.is_match(&tags.to_string())
+    }
+}
+
+/// Charset of a station's titles, by name (e.g. `windows-1251`)
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(try_from = "String")]
+pub struct Charset(&'static Encoding);
+
+impl TryFrom<String> for Charset {
+    type Error = String;
+
+    fn try_from(label: String) -> Result<Self, Self::Error> {
+        charset::for_label(&label)
+            .map(Self)
+            .ok_or_else(|| format!("Unknown encoding: {label}"))
+    }
+}
+
+/// What happens to titles matching an exclude rule
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    /// Not shown at all, and not a song
+    Hide,
+    /// Shown as now playing, but not a song
+    #[default]
+    Skip,
+    /// A song, but it isn't recorded
+    NoRecord,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ExcludeRule {
+    pub pattern: Pattern,
+    #[serde(default)]
+    pub action: RuleAction,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct RuleSet {
+    /// If there are any, only matching titles are songs
+    pub include: Vec<Pattern>,
+    pub exclude: Vec<ExcludeRule>,
+    /// Patterns with `title` and `artist` groups that split titles the separators can't
+    pub split: Vec<Pattern>,
+    /// Charset to read garbled titles in, instead of guessing it
+    pub encoding: Option<Charset>,
+    /// Don't scrobble anything
+    pub no_scrobble: bool,
+}
+
+/// What to do with a new title
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TitleDecision {
+    /// Wether it goes into the queue
+    pub is_song: bool,
+    pub record: bool,
+    /// Wether it is shown as now playing
+    pub display: bool,
+}
+
+/// Rules that decide which titles count as songs, stored in the data directory.
+/// The rules of a station are applied on top of the global ones,
+/// its include list replaces the global one if it has any patterns
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct TitleRules {
+    pub global: RuleSet,
+    /// Rules by station id
+    pub stations: HashMap<String, RuleSet>,
+}
+
+impl TitleRules {
+    pub fn path() -> PathBuf {
+        get_data_dir().join(RULES_FILE)
+    }
+
+    /// Read the stored rules, no rules if there are none.
+    /// Fails if the rules exist, but aren't valid
+    pub fn load() -> io::Result<Self> {
+        match fs::read(Self::path()) {
+            Ok(data) => serde_json::from_slice(&data).map_err(io::Error::other),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Split patterns for a station, its own before the global ones
+    pub fn split_patterns(&self, station_id: &str) -> Vec<Regex> {
+        self.stations
+            .get(station_id)
+            .into_iter()
+            .flat_map(|rules| &rules.split)
+            .chain(&self.global.split)
+            .map(|pattern| pattern.regex().clone())
+            .collect()
+    }
+
+    /// Charset of a station's titles, its own before the global one
+    pub fn encoding(&self, station_id: &str) -> Option<&'static Encoding> {
+        self.stations
+            .get(station_id)
+            .and_then(|rules| rules.encoding)
+            .or(self.global.encoding)
+            .map(|charset| charset.0)
+    }
+
+    /// Wether the songs of a station are scrobbled
+    pub fn scrobbles(&self, station_id: &str) -> bool {
+        !self.global.no_scrobble
+            && !self
+                .stations
+                .get(station_id)
+                .is_some_and(|rules| rules.no_scrobble)
+    }
+
+    pub fn decide(&self, station_id: &str, tags: &TrackTags) -> TitleDecision {
+        let station = self.stations.get(station_id);
+        let include = station
+            .map(|rules| &rules.include)
+            .filter(|include| !include.is_empty())
+            .unwrap_or(&self.global.include);
+
+        let mut decision = TitleDecision {
+            is_song: include.is_empty() || include.iter().any(|pattern| pattern.matches(tags)),
+            record: true,
+            display: true,
+        };
+
+        let exclude = self
+            .global
+            .exclude
+            .iter()
+            .chain(station.into_iter().flat_map(|rules| &rules.exclude));
+
+        for rule in exclude.filter(|rule| rule.pattern.matches(tags)) {
+            match rule.action {
+                RuleAction::Hide => decision.display = false,
+                RuleAction::Skip => decision.is_song = false,
+                RuleAction::NoRecord => decision.record = false,
+            }
+        }
+
+        // Only songs get recorded, and there is no song without a title
+        decision.is_song &= decision.display;
+        decision.record &= decision.is_song;
+        decision
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decisions() {
+        let rules: TitleRules = serde_json::from_str(
+            r#"{
+                "global": {
+                    "exclude": [
+                        { "pattern": "^$", "action": "hide" },
+                        { "pattern": "advertisement" },
+                        { "pattern": "^Live Session", "action": "no_record" }
+                    ]
+                },
+                "stations": {
+                    "jazz": {
+                        "include": [" - "],
+                        "split": ["^(?<title>.+) by (?<artist>.+)$"],
+                        "encoding": "windows-1251",
+                        "exclude": [{ "pattern": "^Jazz FM - Live$" }]
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let decide =
+            |station, title, artist| rules.decide(station, &TrackTags::mock(title, artist));
+        let song = TitleDecision {
+            is_song: true,
+            record: true,
+            display: true,
+        };
+
+        assert_eq!(decide("pop", "Song", Some("Artist")), song);
+        assert_eq!(
+            decide("pop", "", None),
+            TitleDecision {
+                is_song: false,
+                record: false,
+                display: false,
+            }
+        );
+        assert_eq!(
+            decide("pop", "ADVERTISEMENT", None),
+            TitleDecision {
+                is_song: false,
+                record: false,
+                display: true,
+            }
+        );
+        assert_eq!(
+            decide("pop", "Live Session 12", None),
+            TitleDecision {
+                record: false,
+                ..song
+            }
+        );
+
+        // Only `artist - title`s are songs on this station
+        assert_eq!(decide("jazz", "Song", Some("Artist")), song);
+        assert!(!decide("jazz", "Jazz FM", None).is_song);
+        assert!(!decide("jazz", "Jazz FM - Live", None).is_song);
+
+        assert_eq!(rules.split_patterns("jazz").len(), 1);
+        assert!(rules.split_patterns("pop").is_empty());
+        assert_eq!(rules.encoding("jazz"), charset::for_label("cp1251"));
+        assert_eq!(rules.encoding("pop"), None);
+    }
+
+    #[test]
+    fn invalid_rules() {
+        assert!(
+            serde_json::from_str::<TitleRules>(r#"{ "global": { "encoding": "klingon" } }"#)
+                .is_err()
+        );
+        assert!(
+            serde_json::from_str::<TitleRules>(r#"{ "global": { "include": ["("] } }"#).is_err()
+        );
+    }
+}
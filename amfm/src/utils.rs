@@ -1,4 +1,7 @@
+use std::{env, fs, path::PathBuf};
+
 use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use regex::{Regex, RegexBuilder};
 
 pub fn margins(area: Rect, remaining_percentage: u16) -> Rect {
     let [area] = Layout::horizontal([Constraint::Percentage(remaining_percentage)])
@@ -22,3 +25,17 @@ pub fn center_horizontal(area: Rect, width: u16) -> Rect {
         .areas(area);
     area
 }
+
+/// Directory where amfm keeps its persistent data (settings, history...)
+pub fn get_data_dir() -> PathBuf {
+    let dir = dirs::data_dir().unwrap_or_else(env::temp_dir).join("amfm");
+
+    fs::create_dir_all(&dir).expect("Could not create data directory!");
+
+    dir
+}
+
+/// Parses a pattern the user entered, it is matched case insensitively
+pub fn case_insensitive(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
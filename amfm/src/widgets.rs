@@ -1,3 +1,12 @@
+pub mod background_list;
+pub mod equalizer;
+pub mod history_list;
+pub mod level_meter;
+pub mod output_picker;
 pub mod radio_info;
+pub mod schedule_list;
 pub mod shortcuts_display;
+pub mod sleep_timer_picker;
+pub mod spectrum;
 pub mod stations_table;
+pub mod wishlist_panel;
@@ -0,0 +1,69 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, List, ListState, Paragraph},
+};
+
+use crate::{
+    background::BackgroundRecorder,
+    utils::{center_horizontal, center_vertical, margins},
+};
+
+pub struct BackgroundList<'a> {
+    pub recorders: &'a [BackgroundRecorder],
+    pub state: &'a mut ListState,
+}
+
+impl Widget for BackgroundList<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let area = margins(area, 80);
+        let height = (self.recorders.len() as u16 + 4).clamp(8, area.height);
+        let area = center_vertical(center_horizontal(area, area.width), height);
+
+        let block = Block::new()
+            .borders(Borders::all())
+            .title_top("Background Recordings")
+            .title_bottom(
+                Line::from(" [Enter] Save songs  [d] Save and stop ")
+                    .right_aligned()
+                    .light_magenta(),
+            );
+
+        let list = List::new(self.recorders.iter().map(|recorder| {
+            let status = if let Some(err) = &recorder.error {
+                Span::raw(format!(" {err}")).red()
+            } else if recorder.is_recording() {
+                Span::raw(" REC ").white().on_red()
+            } else {
+                Span::raw(" waiting").dim()
+            };
+
+            let title = recorder
+                .now_playing
+                .as_ref()
+                .map_or_else(String::new, ToString::to_string);
+
+            Line::from(vec![
+                Span::raw(format!("{} ", recorder.station.name)).bold(),
+                Span::raw(title),
+                Span::raw(format!(" ({} songs)", recorder.queue.iter().count())).dim(),
+                status,
+            ])
+        }))
+        .highlight_style(Style::new().black().on_white());
+
+        let hint = Paragraph::new("Press [b] on a station to record it in the background")
+            .dim()
+            .italic();
+
+        let [list_area, hint_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(block.inner(area));
+
+        Clear.render(area, buf);
+        StatefulWidget::render(list, list_area, buf, self.state);
+        hint.render(hint_area, buf);
+        block.render(area, buf);
+    }
+}
@@ -0,0 +1,94 @@
+use antenna::equalizer::{self, Gains, Preset};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::utils::{center_horizontal, center_vertical};
+
+/// Width of a single band column
+const BAND_WIDTH: u16 = 5;
+
+pub struct EqualizerPanel<'a> {
+    pub gains: &'a Gains,
+    pub selected_band: usize,
+}
+
+impl Widget for EqualizerPanel<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let width = BAND_WIDTH * equalizer::BANDS as u16 + 2;
+        let area = center_vertical(center_horizontal(area, width), 16.min(area.height));
+
+        let preset = Preset::matching(self.gains).map_or("Custom", Preset::name);
+        let block = Block::new()
+            .borders(Borders::all())
+            .title_top("Equalizer")
+            .title_bottom(Line::from(format!(" {preset} ")).centered())
+            .title_bottom(Line::from(" [p] Preset ").right_aligned().light_magenta());
+
+        Clear.render(area, buf);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let [bars_area, gain_area, frequency_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+
+        let columns =
+            Layout::horizontal(vec![Constraint::Length(BAND_WIDTH); equalizer::BANDS]).split(inner);
+
+        let range = equalizer::MAX_GAIN - equalizer::MIN_GAIN;
+        let zero_row = (bars_area.height as f64 * -equalizer::MIN_GAIN / range).round() as u16;
+
+        for (band, (gain, column)) in self.gains.iter().zip(columns.iter()).enumerate() {
+            let style = if band == self.selected_band {
+                Style::new().cyan().bold()
+            } else {
+                Style::new().white()
+            };
+
+            // Bar, growing from the bottom
+            let filled =
+                (bars_area.height as f64 * (gain - equalizer::MIN_GAIN) / range).round() as u16;
+            let x = column.x + BAND_WIDTH / 2;
+            for row in 0..bars_area.height {
+                let y = bars_area.bottom() - 1 - row;
+                if row < filled {
+                    buf[(x, y)].set_symbol("█").set_style(style);
+                } else if row == zero_row {
+                    buf[(x, y)].set_symbol("·").set_style(Style::new().dim());
+                }
+            }
+
+            Paragraph::new(format!("{gain:+.0}"))
+                .style(style)
+                .centered()
+                .render(
+                    Rect {
+                        y: gain_area.y,
+                        height: 1,
+                        ..*column
+                    },
+                    buf,
+                );
+
+            Paragraph::new(equalizer::FREQUENCIES[band])
+                .style(style.dim())
+                .centered()
+                .render(
+                    Rect {
+                        y: frequency_area.y,
+                        height: 1,
+                        ..*column
+                    },
+                    buf,
+                );
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, List, ListState, Paragraph},
+};
+
+use crate::{
+    history::{History, TrackStatus},
+    utils::{center_horizontal, center_vertical, margins},
+};
+
+pub struct HistoryList<'a> {
+    pub history: &'a History,
+    pub search: &'a str,
+    pub state: &'a mut ListState,
+}
+
+impl Widget for HistoryList<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let area = margins(area, 80);
+        let area = center_vertical(center_horizontal(area, area.width), area.height);
+
+        let block = Block::new()
+            .borders(Borders::all())
+            .title_top("History")
+            .title_bottom(
+                Line::from(" [Enter] Play station  [Tab] Copy track ")
+                    .right_aligned()
+                    .light_magenta(),
+            );
+
+        let list = List::new(self.history.search(self.search).map(|entry| {
+            let status = match entry.status {
+                TrackStatus::Heard => Span::raw(""),
+                TrackStatus::Recorded => Span::raw(" recorded").dim(),
+                TrackStatus::Saved => Span::raw(" saved").green(),
+            };
+
+            Line::from(vec![
+                Span::raw(entry.time.format("%Y-%m-%d %H:%M ").to_string()).dim(),
+                Span::raw(format!("{} ", entry.track())).bold(),
+                Span::raw(entry.station_name.clone()).cyan(),
+                status,
+            ])
+        }))
+        .highlight_style(Style::new().black().on_white());
+
+        let search = if self.search.is_empty() {
+            Paragraph::new("Type to search").dim().italic()
+        } else {
+            Paragraph::new(format!("> {}", self.search)).cyan()
+        };
+
+        let [search_area, list_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(block.inner(area));
+
+        Clear.render(area, buf);
+        search.render(search_area, buf);
+        StatefulWidget::render(list, list_area, buf, self.state);
+        block.render(area, buf);
+    }
+}
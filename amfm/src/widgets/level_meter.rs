@@ -0,0 +1,46 @@
+use antenna::playback::AudioLevel;
+use ratatui::prelude::*;
+use ratatui::widgets::{LineGauge, Widget};
+
+/// Lowest level (dB) the meter can show
+const MIN_DB: f64 = -60.0;
+
+/// VU meter with one gauge per channel
+pub struct LevelMeter<'a> {
+    pub level: &'a AudioLevel,
+}
+
+impl Widget for LevelMeter<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let channels = self.level.rms.len();
+        let areas = Layout::vertical(vec![Constraint::Length(1); channels]).split(area);
+
+        for (i, (area, rms)) in areas.iter().zip(&self.level.rms).enumerate() {
+            let peak = self.level.peak.get(i).copied().unwrap_or(*rms);
+
+            let label = match (channels, i) {
+                (2, 0) => "L".to_string(),
+                (2, 1) => "R".to_string(),
+                _ => (i + 1).to_string(),
+            };
+
+            let color = if peak > -3.0 {
+                Color::Red
+            } else if peak > -9.0 {
+                Color::Yellow
+            } else {
+                Color::Green
+            };
+
+            LineGauge::default()
+                .ratio(((rms - MIN_DB) / -MIN_DB).clamp(0.0, 1.0))
+                .label(label)
+                .filled_style(Style::new().fg(color))
+                .unfilled_style(Style::new().dim())
+                .render(*area, buf);
+        }
+    }
+}
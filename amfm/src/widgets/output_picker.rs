@@ -0,0 +1,48 @@
+use antenna::output::{AudioSink, OutputDevice};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, List, ListState},
+};
+
+use crate::utils::{center_horizontal, center_vertical};
+
+pub struct OutputPicker<'a> {
+    pub outputs: &'a [OutputDevice],
+    pub current: &'a AudioSink,
+    pub state: &'a mut ListState,
+}
+
+impl Widget for OutputPicker<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let width = self
+            .outputs
+            .iter()
+            .map(|output| output.name.len() as u16 + 6)
+            .max()
+            .unwrap_or_default()
+            .clamp(30, area.width);
+        let height = (self.outputs.len() as u16 + 2).min(area.height);
+
+        let area = center_vertical(center_horizontal(area, width), height);
+
+        let block = Block::new()
+            .borders(Borders::all())
+            .title_top("Audio Output");
+
+        let list = List::new(self.outputs.iter().map(|output| {
+            if &output.sink == self.current {
+                Span::raw(format!("* {}", output.name)).bold()
+            } else {
+                Span::raw(format!("  {}", output.name))
+            }
+        }))
+        .highlight_style(Style::new().black().on_white());
+
+        Clear.render(area, buf);
+        StatefulWidget::render(list, block.inner(area), buf, self.state);
+        block.render(area, buf);
+    }
+}
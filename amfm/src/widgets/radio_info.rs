@@ -1,18 +1,33 @@
-use antenna::playback::PlaybackUpdate;
+use antenna::playback::{AudioLevel, PlaybackUpdate, StreamInfo, TrackTags};
+use std::time::Duration;
+
 use ratatui::prelude::*;
 use ratatui::widgets::{Paragraph, Widget, Wrap};
 
-use crate::song_queue::Song;
 use crate::utils::center_horizontal;
+use crate::widgets::{level_meter::LevelMeter, spectrum::SpectrumBars};
+
+/// Height of the spectrum visualizer
+const SPECTRUM_HEIGHT: u16 = 3;
 
 const RECORDING_TEXT: &str = "REC";
+const SESSION_RECORDING_TEXT: &str = "REC SESSION";
 const NOT_RECORDING_TEXT: &str = "IDLE";
 
+/// Streams below this bitrate (bits per second) are highlighted
+const LOW_BITRATE: u32 = 64_000;
+
 pub struct RadioInfo<'a> {
     pub name: &'a str,
-    pub current_song: Option<&'a Song>,
+    pub current_song: Option<&'a TrackTags>,
     pub is_recording: bool,
+    /// Wether the recording is a whole session instead of a single song
+    pub is_session_recording: bool,
     pub last_update: &'a PlaybackUpdate,
+    pub stream_info: Option<&'a StreamInfo>,
+    pub level: Option<&'a AudioLevel>,
+    pub spectrum: Option<&'a [f32]>,
+    pub sleep_remaining: Option<Duration>,
 }
 
 impl Widget for RadioInfo<'_> {
@@ -40,7 +55,9 @@ impl Widget for RadioInfo<'_> {
             .wrap(Wrap { trim: true });
 
         // Recording Indicator
-        let rec_text = if self.is_recording {
+        let rec_text = if self.is_session_recording {
+            SESSION_RECORDING_TEXT
+        } else if self.is_recording {
             RECORDING_TEXT
         } else {
             NOT_RECORDING_TEXT
@@ -53,9 +70,33 @@ impl Widget for RadioInfo<'_> {
             recording = recording.black().on_white();
         }
 
+        // Stream info
+        let stream_info = self
+            .stream_info
+            .map(stream_info_line)
+            .unwrap_or_default()
+            .centered();
+
+        // Sleep timer
+        let sleep_timer = self
+            .sleep_remaining
+            .map(|remaining| {
+                let secs = remaining.as_secs();
+                Paragraph::new(format!("Sleeping in {}:{:02}", secs / 60, secs % 60))
+                    .dim()
+                    .centered()
+            })
+            .unwrap_or_default();
+
         // Last Update
         let last_update = match self.last_update {
             PlaybackUpdate::Loading => Paragraph::new("Loading...").dim().italic().centered(),
+            PlaybackUpdate::Buffering(percent) if *percent < 100 => {
+                Paragraph::new(format!("Buffering... {percent}%"))
+                    .dim()
+                    .italic()
+                    .centered()
+            }
             PlaybackUpdate::Stopped => Paragraph::new("Stopped.").dim().italic().centered(),
             PlaybackUpdate::Error(msg) => Paragraph::new(msg.as_str())
                 .red()
@@ -68,12 +109,24 @@ impl Widget for RadioInfo<'_> {
             name_area,
             current_song_area,
             recording_area,
+            stream_info_area,
+            sleep_timer_area,
             last_update_area,
+            level_area,
+            spectrum_area,
         ] = Layout::vertical([
             Constraint::Length(name.line_count(area.width) as u16),
             Constraint::Length(current_song.line_count(area.width) as u16),
             Constraint::Length(1),
+            Constraint::Length(if stream_info.spans.is_empty() { 0 } else { 1 }),
+            Constraint::Length(self.sleep_remaining.map_or(0, |_| 1)),
             Constraint::Length(last_update.line_count(area.width) as u16),
+            Constraint::Length(self.level.map_or(0, |level| level.rms.len() as u16)),
+            Constraint::Length(if self.spectrum.is_some() {
+                SPECTRUM_HEIGHT
+            } else {
+                0
+            }),
         ])
         .areas(area);
 
@@ -83,6 +136,57 @@ impl Widget for RadioInfo<'_> {
             center_horizontal(recording_area, rec_text.len() as u16),
             buf,
         );
+        stream_info.render(stream_info_area, buf);
+        sleep_timer.render(sleep_timer_area, buf);
         last_update.render(last_update_area, buf);
+
+        if let Some(level) = self.level {
+            LevelMeter { level }.render(level_area, buf);
+        }
+
+        if let Some(magnitudes) = self.spectrum {
+            SpectrumBars { magnitudes }.render(spectrum_area, buf);
+        }
+    }
+}
+
+/// Formats stream info like "MP3 · 128 kbps · 44.1 kHz · Stereo"
+fn stream_info_line(info: &StreamInfo) -> Line<'static> {
+    let mut spans = vec![];
+
+    if let Some(codec) = &info.codec {
+        spans.push(Span::raw(codec.clone()));
+    }
+
+    if let Some(bitrate) = info.bitrate {
+        let span = Span::raw(format!("{} kbps", bitrate / 1000));
+        spans.push(if bitrate < LOW_BITRATE {
+            span.yellow()
+        } else {
+            span
+        });
     }
+
+    if let Some(rate) = info.sample_rate {
+        spans.push(Span::raw(format!("{} kHz", f64::from(rate) / 1000.0)));
+    }
+
+    if let Some(channels) = info.channels {
+        spans.push(Span::raw(match channels {
+            1 => "Mono".to_string(),
+            2 => "Stereo".to_string(),
+            n => format!("{n} ch"),
+        }));
+    }
+
+    let separated = spans
+        .into_iter()
+        .enumerate()
+        .flat_map(|(i, span)| {
+            let separator = (i > 0).then(|| Span::raw(" · "));
+            separator.into_iter().chain([span])
+        })
+        .collect::<Vec<_>>();
+
+    Line::from(separated).dim()
 }
@@ -0,0 +1,85 @@
+use chrono::NaiveDateTime;
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, List, ListState, Paragraph, Wrap},
+};
+
+use crate::{
+    schedule::{Schedule, ScheduleEntry},
+    utils::{center_horizontal, center_vertical, margins},
+};
+
+pub struct ScheduleList<'a> {
+    pub schedule: &'a Schedule,
+    /// Entry that is being recorded right now
+    pub recording: Option<&'a ScheduleEntry>,
+    /// Text of the schedule that is being typed in
+    pub input: Option<&'a str>,
+    pub error: Option<&'a str>,
+    pub now: NaiveDateTime,
+    pub state: &'a mut ListState,
+}
+
+impl Widget for ScheduleList<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let area = margins(area, 80);
+        let height = (self.schedule.iter().count() as u16 + 7).clamp(10, area.height);
+        let area = center_vertical(center_horizontal(area, area.width), height);
+
+        let block = Block::new()
+            .borders(Borders::all())
+            .title_top("Scheduled Recordings")
+            .title_bottom(
+                Line::from(" [a] Add  [Enter] Edit  [d] Delete ")
+                    .right_aligned()
+                    .light_magenta(),
+            );
+
+        let list = List::new(self.schedule.iter().map(|entry| {
+            let status = if self.recording == Some(entry) {
+                Span::raw(" REC ").white().on_red()
+            } else {
+                match entry.next_after(self.now) {
+                    Some(next) => Span::raw(next.format(" next %a %d %b %H:%M").to_string()).dim(),
+                    None => Span::raw(" done").dim(),
+                }
+            };
+
+            Line::from(vec![
+                Span::raw(format!("{} ", entry.station_name)).bold(),
+                Span::raw(entry.spec()),
+                status,
+            ])
+        }))
+        .highlight_style(Style::new().black().on_white());
+
+        let input = match self.input {
+            Some(input) => Paragraph::new(format!("> {input}")).cyan(),
+            None => {
+                Paragraph::new("<HH:MM> <minutes> <daily|weekdays|weekends|mon..sun|YYYY-MM-DD>")
+                    .dim()
+                    .italic()
+            }
+        };
+
+        let error = Paragraph::new(self.error.unwrap_or_default())
+            .red()
+            .wrap(Wrap { trim: true });
+
+        let [list_area, input_area, error_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(2),
+        ])
+        .areas(block.inner(area));
+
+        Clear.render(area, buf);
+        StatefulWidget::render(list, list_area, buf, self.state);
+        input.render(input_area, buf);
+        error.render(error_area, buf);
+        block.render(area, buf);
+    }
+}
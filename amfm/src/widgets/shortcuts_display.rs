@@ -13,9 +13,20 @@ impl Widget for ShortcutsDisplay {
     {
         let shortcuts = [
             shortcut("/", "Search"),
+            shortcut("f", "Favorite"),
+            shortcut("F", "Favorites"),
+            shortcut("R", "Recent"),
             shortcut("s", "Stop playback"),
             shortcut("q", "Quit"),
             shortcut("y", "Copy URL"),
+            shortcut("o", "Output"),
+            shortcut("e", "Equalizer"),
+            shortcut("t", "Sleep timer"),
+            shortcut("r", "Schedule"),
+            shortcut("c", "Session rec"),
+            shortcut("B", "Background rec"),
+            shortcut("W", "Wishlist"),
+            shortcut("h", "History"),
         ];
 
         let constraints = shortcuts.iter().map(|s| Constraint::Length(s.0 as u16));
@@ -0,0 +1,52 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, List, ListState, Paragraph},
+};
+
+use crate::{
+    sleep_timer::PRESETS,
+    utils::{center_horizontal, center_vertical},
+};
+
+pub struct SleepTimerPicker<'a> {
+    /// Minutes the user typed in, takes priority over the selected preset
+    pub custom_minutes: &'a str,
+    pub state: &'a mut ListState,
+}
+
+impl Widget for SleepTimerPicker<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let height = (PRESETS.len() as u16 + 4).min(area.height);
+        let area = center_vertical(center_horizontal(area, 30), height);
+
+        let block = Block::new()
+            .borders(Borders::all())
+            .title_top("Sleep Timer");
+
+        let [list_area, _, custom_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(block.inner(area));
+
+        let list = List::new(PRESETS.iter().map(|minutes| format!("{minutes} min")))
+            .highlight_style(Style::new().black().on_white());
+
+        let custom = if self.custom_minutes.is_empty() {
+            Paragraph::new("Type minutes for a custom time")
+                .dim()
+                .italic()
+        } else {
+            Paragraph::new(format!("Custom: {} min", self.custom_minutes)).cyan()
+        };
+
+        Clear.render(area, buf);
+        StatefulWidget::render(list, list_area, buf, self.state);
+        custom.render(custom_area, buf);
+        block.render(area, buf);
+    }
+}
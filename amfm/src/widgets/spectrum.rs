@@ -0,0 +1,32 @@
+use antenna::playback::SPECTRUM_THRESHOLD;
+use ratatui::prelude::*;
+use ratatui::widgets::{Sparkline, Widget};
+
+/// Bar visualizer of the magnitudes of `PlaybackUpdate::Spectrum`
+pub struct SpectrumBars<'a> {
+    pub magnitudes: &'a [f32],
+}
+
+impl Widget for SpectrumBars<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        if self.magnitudes.is_empty() || area.width == 0 {
+            return;
+        }
+
+        // Stretch (or squash) the bands over the full width
+        let bands = self.magnitudes.len();
+        let bars = (0..area.width as usize).map(|x| {
+            let magnitude = self.magnitudes[x * bands / area.width as usize];
+            (magnitude - SPECTRUM_THRESHOLD as f32).max(0.0) as u64
+        });
+
+        Sparkline::default()
+            .data(bars)
+            .max(SPECTRUM_THRESHOLD.unsigned_abs().into())
+            .magenta()
+            .render(area, buf);
+    }
+}
@@ -5,10 +5,13 @@ use ratatui::{
     widgets::{Row, StatefulWidget, Table, TableState, Widget},
 };
 
+use crate::favorites::Favorites;
+
 pub struct StationsTable<'a> {
     pub stations: Box<dyn Iterator<Item = &'a Station> + 'a>,
     pub state: &'a mut TableState,
     pub focused: bool,
+    pub favorites: &'a Favorites,
 }
 
 impl Widget for StationsTable<'_> {
@@ -16,9 +19,15 @@ impl Widget for StationsTable<'_> {
     where
         Self: Sized,
     {
-        let rows = self.stations.map(|s| station_to_row(s));
+        let rows = self
+            .stations
+            .map(|s| station_to_row(s, self.favorites.contains(&s.id)));
 
-        let widths = [Constraint::Fill(1), Constraint::Max(30)];
+        let widths = [
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Max(30),
+        ];
 
         let table = Table::new(rows, widths)
             .column_spacing(1)
@@ -34,9 +43,10 @@ impl Widget for StationsTable<'_> {
 }
 
 fn header<'a>() -> Row<'a> {
-    Row::new(vec!["Name", "Country"]).black().on_white()
+    Row::new(vec!["", "Name", "Country"]).black().on_white()
 }
 
-fn station_to_row(station: &Station) -> Row<'_> {
-    Row::new(vec![station.name.as_str(), station.country.as_str()])
+fn station_to_row(station: &Station, favorite: bool) -> Row<'_> {
+    let star = if favorite { "★" } else { "" };
+    Row::new(vec![star, station.name.as_str(), station.country.as_str()])
 }
@@ -0,0 +1,79 @@
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, List, ListState, Paragraph, Wrap},
+};
+
+use crate::{
+    utils::{center_horizontal, center_vertical, margins},
+    wishlist::Wishlist,
+};
+
+pub struct WishlistPanel<'a> {
+    pub wishlist: &'a Wishlist,
+    /// Text of the pattern that is being typed in
+    pub input: Option<&'a str>,
+    pub error: Option<&'a str>,
+    pub state: &'a mut ListState,
+}
+
+impl Widget for WishlistPanel<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let area = margins(area, 80);
+        let height = (self.wishlist.patterns().count() as u16 + 8).clamp(11, area.height);
+        let area = center_vertical(center_horizontal(area, area.width), height);
+
+        let block = Block::new()
+            .borders(Borders::all())
+            .title_top("Wishlist")
+            .title_bottom(
+                Line::from(" [a] Add  [d] Delete ")
+                    .right_aligned()
+                    .light_magenta(),
+            );
+
+        let list = List::new(self.wishlist.patterns().map(Span::raw))
+            .highlight_style(Style::new().black().on_white());
+
+        let names: Vec<&str> = self
+            .wishlist
+            .stations()
+            .map(|station| station.name.as_str())
+            .collect();
+        let stations = if names.is_empty() {
+            Paragraph::new("No stations watched, press [w] on a station to watch it")
+        } else {
+            Paragraph::new(format!("Watching: {}", names.join(", ")))
+        }
+        .dim()
+        .wrap(Wrap { trim: true });
+
+        let input = match self.input {
+            Some(input) => Paragraph::new(format!("> {input}")).cyan(),
+            None => Paragraph::new("<artist or song, regexes work too>")
+                .dim()
+                .italic(),
+        };
+
+        let error = Paragraph::new(self.error.unwrap_or_default())
+            .red()
+            .wrap(Wrap { trim: true });
+
+        let [list_area, stations_area, input_area, error_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(2),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(block.inner(area));
+
+        Clear.render(area, buf);
+        StatefulWidget::render(list, list_area, buf, self.state);
+        stations.render(stations_area, buf);
+        input.render(input_area, buf);
+        error.render(error_area, buf);
+        block.render(area, buf);
+    }
+}
@@ -0,0 +1,161 @@
+use std::{fs, io, path::PathBuf, sync::Arc};
+
+use antenna::{playback::TrackTags, stations::Station};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{case_insensitive, get_data_dir};
+
+const WISHLIST_FILE: &str = "wishlist.json";
+
+/// A station whose titles are watched for wished songs
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct WatchedStation {
+    pub id: String,
+    /// Only used for display, the station is looked up by id
+    pub name: String,
+}
+
+/// Artists and songs the user is waiting for, and the stations that are watched for them.
+/// Stored in the data directory
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(default)]
+pub struct Wishlist {
+    /// Case insensitive regexes, matched against the title and `artist - title`
+    patterns: Vec<String>,
+    stations: Vec<WatchedStation>,
+    #[serde(skip)]
+    regexes: Vec<Regex>,
+}
+
+/// A wished song that is on air
+#[derive(Debug, Clone, PartialEq)]
+pub struct WishlistAlert {
+    pub station_id: String,
+    pub station_name: String,
+    pub tags: Arc<TrackTags>,
+    /// The pattern that matched
+    pub pattern: String,
+}
+
+impl Wishlist {
+    pub fn path() -> PathBuf {
+        get_data_dir().join(WISHLIST_FILE)
+    }
+
+    /// Read the stored wishlist, an empty one if there is none.
+    /// Patterns that aren't valid (anymore) are dropped
+    pub fn load() -> io::Result<Self> {
+        let mut wishlist: Self = match fs::read(Self::path()) {
+            Ok(data) => serde_json::from_slice(&data).map_err(io::Error::other)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(err) => return Err(err),
+        };
+
+        wishlist
+            .patterns
+            .retain(|pattern| case_insensitive(pattern).is_ok());
+        wishlist.regexes = wishlist
+            .patterns
+            .iter()
+            .filter_map(|pattern| case_insensitive(pattern).ok())
+            .collect();
+
+        Ok(wishlist)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let data = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
+        fs::write(Self::path(), data)
+    }
+
+    pub fn add_pattern(&mut self, pattern: &str) -> Result<(), String> {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            return Err("Enter an artist or song".to_string());
+        }
+
+        let regex = case_insensitive(pattern).map_err(|err| format!("Invalid pattern: {err}"))?;
+        self.patterns.push(pattern.to_string());
+        self.regexes.push(regex);
+
+        Ok(())
+    }
+
+    pub fn remove_pattern(&mut self, index: usize) {
+        if index < self.patterns.len() {
+            self.patterns.remove(index);
+            self.regexes.remove(index);
+        }
+    }
+
+    pub fn patterns(&self) -> impl Iterator<Item = &str> {
+        self.patterns.iter().map(String::as_str)
+    }
+
+    pub fn stations(&self) -> impl Iterator<Item = &WatchedStation> {
+        self.stations.iter()
+    }
+
+    pub fn is_watched(&self, station_id: &str) -> bool {
+        self.stations.iter().any(|station| station.id == station_id)
+    }
+
+    /// Start or stop watching `station`, returns wether it is watched now
+    pub fn toggle_station(&mut self, station: &Station) -> bool {
+        if self.is_watched(&station.id) {
+            self.stations.retain(|watched| watched.id != station.id);
+            false
+        } else {
+            self.stations.push(WatchedStation {
+                id: station.id.clone(),
+                name: station.name.clone(),
+            });
+            true
+        }
+    }
+
+    /// The first pattern that `tags` match
+    pub fn matching(&self, tags: &TrackTags) -> Option<&str> {
+        let full = tags.to_string();
+
+        self.regexes
+            .iter()
+            .position(|regex| regex.is_match(&tags.title) || regex.is_match(&full))
+            .map(|index| self.patterns[index].as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_patterns() {
+        let mut wishlist = Wishlist::default();
+        wishlist.add_pattern("daft punk").unwrap();
+        wishlist.add_pattern("^Around the World$").unwrap();
+        assert!(wishlist.add_pattern("  ").is_err());
+        assert!(wishlist.add_pattern("(unclosed").is_err());
+
+        assert_eq!(
+            wishlist.matching(&TrackTags::mock("One More Time", Some("Daft Punk"))),
+            Some("daft punk")
+        );
+        assert_eq!(
+            wishlist.matching(&TrackTags::mock("around the world", Some("Someone Else"))),
+            Some("^Around the World$")
+        );
+        assert_eq!(
+            wishlist.matching(&TrackTags::mock("D.A.N.C.E.", Some("Justice"))),
+            None
+        );
+
+        wishlist.remove_pattern(0);
+        assert_eq!(
+            wishlist.matching(&TrackTags::mock("One More Time", Some("Daft Punk"))),
+            None
+        );
+        assert_eq!(wishlist.patterns().count(), 1);
+    }
+}
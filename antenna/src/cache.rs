@@ -6,6 +6,8 @@ use std::{
 };
 
 use crate::{
+    charset,
+    network::{NetworkError, NetworkSettings},
     stations::Station,
     utils::{self},
 };
@@ -20,6 +22,7 @@ pub enum CacheError {
     BinEncodeError(bincode::error::EncodeError),
     BinDecodeError(bincode::error::DecodeError),
     JsonDecodeError(serde_json::Error),
+    ClientError(NetworkError),
 }
 
 pub type CacheResultHandle = thread::JoinHandle<Result<Vec<Station>, CacheError>>;
@@ -34,11 +37,12 @@ pub struct CacheResult {
 
 /// Download and store the json file containing all stations.
 /// Then, read and convert said json file into a .bin file for faster loading times
-pub fn make_cache() -> CacheResult {
+pub fn make_cache(network: &NetworkSettings) -> CacheResult {
     let (tx, rx) = mpsc::channel();
+    let network = network.clone();
 
     let handle: CacheResultHandle = thread::spawn(move || {
-        let client = reqwest::blocking::Client::new();
+        let client = network.client().map_err(CacheError::ClientError)?;
         let response = client
             .get(STATIONS_URL)
             .send()
@@ -91,9 +95,9 @@ fn create_bin() -> Result<Vec<Station>, CacheError> {
     let mut data: Vec<Station> =
         serde_json::from_reader(reader).map_err(CacheError::JsonDecodeError)?;
 
-    // Trim all station names
+    // Repair garbled and trim all station names
     for station in &mut data {
-        station.name = station.name.trim().to_string();
+        station.name = charset::repair(&station.name, None).trim().to_string();
     }
 
     // Sort from most votes to least
@@ -0,0 +1,103 @@
+use chardetng::EncodingDetector;
+pub use encoding_rs::Encoding;
+use encoding_rs::WINDOWS_1252;
+
+/// Looks up an encoding by name, e.g. `windows-1251` or `latin1`
+pub fn for_label(label: &str) -> Option<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+}
+
+/// Repairs text that was decoded with the wrong charset on its way to us.
+///
+/// Text in a legacy charset that was read as Latin-1 is decoded again with `encoding`
+/// if it is known, or with the charset it most likely is. UTF-8 that was read as Latin-1
+/// (`CafÃ©`) is always repaired. Anything else is returned as is
+pub fn repair(text: &str, encoding: Option<&'static Encoding>) -> String {
+    if text.is_ascii() {
+        return text.to_string();
+    }
+
+    // Anything that doesn't fit into a single byte charset was decoded right
+    let Some(bytes) = latin1_bytes(text) else {
+        return text.to_string();
+    };
+
+    if let Ok(utf8) = String::from_utf8(bytes.clone()) {
+        return utf8;
+    }
+
+    let encoding = encoding.or_else(|| looks_garbled(text).then(|| detect(&bytes)));
+    match encoding {
+        Some(encoding) => {
+            let (decoded, had_errors) = encoding.decode_without_bom_handling(&bytes);
+            if had_errors {
+                text.to_string()
+            } else {
+                decoded.into_owned()
+            }
+        }
+        None => text.to_string(),
+    }
+}
+
+/// The bytes `text` was decoded from, assuming it was decoded as Latin-1 or Windows-1252
+fn latin1_bytes(text: &str) -> Option<Vec<u8>> {
+    text.chars()
+        .map(|c| {
+            u8::try_from(u32::from(c)).ok().or_else(|| {
+                let mut buf = [0; 4];
+                let (bytes, _, had_errors) = WINDOWS_1252.encode(c.encode_utf8(&mut buf));
+                (!had_errors && bytes.len() == 1).then(|| bytes[0])
+            })
+        })
+        .collect()
+}
+
+/// Real Latin-1 text is mostly ASCII letters,
+/// other scripts read as Latin-1 are mostly accented ones (`Ðàäèî`)
+fn looks_garbled(text: &str) -> bool {
+    let (ascii, other) =
+        text.chars()
+            .filter(|c| c.is_alphabetic())
+            .fold((0, 0), |(ascii, other), c| {
+                if c.is_ascii() {
+                    (ascii + 1, other)
+                } else {
+                    (ascii, other + 1)
+                }
+            });
+
+    other > ascii
+}
+
+fn detect(bytes: &[u8]) -> &'static Encoding {
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(None, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn double_utf8() {
+        assert_eq!(repair("CafÃ© del Mar", None), "Café del Mar");
+        assert_eq!(repair("Donâ€™t Stop", None), "Don’t Stop");
+    }
+
+    #[test]
+    fn legacy_charsets() {
+        // Windows-1251 read as Latin-1
+        assert_eq!(repair("Ðàäèî Ðîññèè", None), "Радио России");
+        assert_eq!(repair("Êèíî", for_label("windows-1251")), "Кино");
+    }
+
+    #[test]
+    fn correct_text() {
+        assert_eq!(repair("Für Elise", None), "Für Elise");
+        assert_eq!(repair("Sigur Rós", None), "Sigur Rós");
+        assert_eq!(repair("גלגלצ", None), "גלגלצ");
+        assert_eq!(repair("Plain", for_label("windows-1251")), "Plain");
+    }
+}
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+
+/// Amount of bands of the equalizer
+pub const BANDS: usize = 10;
+
+/// Lowest gain (dB) a band can have
+pub const MIN_GAIN: f64 = -24.0;
+/// Highest gain (dB) a band can have
+pub const MAX_GAIN: f64 = 12.0;
+
+/// Center frequencies of the bands, for display
+pub const FREQUENCIES: [&str; BANDS] = [
+    "29", "59", "119", "237", "474", "947", "1.9k", "3.8k", "7.5k", "15k",
+];
+
+/// Gain (dB) of every band, from low to high
+pub type Gains = [f64; BANDS];
+
+/// Named sets of band gains
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    Flat,
+    Bass,
+    Treble,
+    Voice,
+    Rock,
+    Classical,
+}
+
+impl Preset {
+    pub const ALL: [Self; 6] = [
+        Self::Flat,
+        Self::Bass,
+        Self::Treble,
+        Self::Voice,
+        Self::Rock,
+        Self::Classical,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Flat => "Flat",
+            Self::Bass => "Bass",
+            Self::Treble => "Treble",
+            Self::Voice => "Voice",
+            Self::Rock => "Rock",
+            Self::Classical => "Classical",
+        }
+    }
+
+    pub fn gains(self) -> Gains {
+        match self {
+            Self::Flat => [0.0; BANDS],
+            Self::Bass => [6.0, 5.0, 4.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            Self::Treble => [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 2.0, 4.0, 5.0, 6.0],
+            Self::Voice => [-4.0, -3.0, -1.0, 1.0, 3.0, 4.0, 3.0, 1.0, -1.0, -3.0],
+            Self::Rock => [4.0, 3.0, 1.0, -1.0, -2.0, -1.0, 1.0, 3.0, 4.0, 4.0],
+            Self::Classical => [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, -2.0, -3.0, -3.0, -4.0],
+        }
+    }
+
+    /// The preset that has exactly these gains, if there is one
+    pub fn matching(gains: &Gains) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.gains() == *gains)
+    }
+
+    /// The preset after this one, wrapping around
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|p| *p == self)
+            .unwrap_or_default();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_in_range() {
+        for preset in Preset::ALL {
+            assert!(
+                preset
+                    .gains()
+                    .iter()
+                    .all(|gain| (MIN_GAIN..=MAX_GAIN).contains(gain))
+            );
+        }
+    }
+
+    #[test]
+    fn matching_preset() {
+        assert_eq!(Preset::matching(&[0.0; BANDS]), Some(Preset::Flat));
+        assert_eq!(
+            Preset::matching(&Preset::Voice.gains()),
+            Some(Preset::Voice)
+        );
+        assert_eq!(Preset::matching(&[1.0; BANDS]), None);
+        assert_eq!(Preset::Classical.next(), Preset::Flat);
+    }
+}
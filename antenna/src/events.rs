@@ -0,0 +1,288 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Condvar, Mutex, Weak,
+        mpsc::{RecvError, RecvTimeoutError, TryRecvError},
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::playback::PlaybackUpdate;
+
+/// How many events a subscriber can fall behind by default
+pub const DEFAULT_CAPACITY: usize = 256;
+
+/// A `PlaybackUpdate`, and when it happened
+#[derive(Debug, Clone)]
+pub struct PlaybackEvent {
+    pub time: SystemTime,
+    pub update: PlaybackUpdate,
+}
+
+/// Events that are replaced by newer ones anyway, dropped first when a subscriber falls behind
+const fn is_transient(update: &PlaybackUpdate) -> bool {
+    matches!(
+        update,
+        PlaybackUpdate::Level(_) | PlaybackUpdate::Spectrum(_) | PlaybackUpdate::Buffering(_)
+    )
+}
+
+#[derive(Debug)]
+struct QueueState {
+    events: VecDeque<PlaybackEvent>,
+    capacity: usize,
+    /// Events that were dropped because the subscriber fell behind
+    dropped: u64,
+    /// Set once the bus is gone, no more events will arrive
+    closed: bool,
+}
+
+#[derive(Debug)]
+struct Queue {
+    state: Mutex<QueueState>,
+    ready: Condvar,
+}
+
+impl Queue {
+    fn push(&self, event: PlaybackEvent) {
+        let mut state = self.state.lock().unwrap();
+
+        if state.events.len() >= state.capacity {
+            // Make room by dropping the oldest transient event, the oldest event otherwise
+            let index = state
+                .events
+                .iter()
+                .position(|event| is_transient(&event.update))
+                .unwrap_or(0);
+            state.events.remove(index);
+            state.dropped += 1;
+        }
+
+        state.events.push_back(event);
+        self.ready.notify_one();
+    }
+
+    fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.ready.notify_all();
+    }
+}
+
+#[derive(Debug, Default)]
+struct BusInner {
+    subscribers: Mutex<Vec<Weak<Queue>>>,
+}
+
+impl Drop for BusInner {
+    fn drop(&mut self) {
+        for queue in self.subscribers.get_mut().unwrap().drain(..) {
+            if let Some(queue) = queue.upgrade() {
+                queue.close();
+            }
+        }
+    }
+}
+
+/// Hands every published update to all of its subscribers.
+/// Publishing never blocks and never fails, subscribers that fall behind lose events instead
+#[derive(Debug, Clone, Default)]
+pub struct EventBus {
+    inner: Arc<BusInner>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(&self) -> Subscription {
+        self.subscribe_with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// Subscribe, keeping at most `capacity` events that weren't received yet
+    pub fn subscribe_with_capacity(&self, capacity: usize) -> Subscription {
+        let queue = Arc::new(Queue {
+            state: Mutex::new(QueueState {
+                events: VecDeque::new(),
+                capacity: capacity.max(1),
+                dropped: 0,
+                closed: false,
+            }),
+            ready: Condvar::new(),
+        });
+
+        self.inner
+            .subscribers
+            .lock()
+            .unwrap()
+            .push(Arc::downgrade(&queue));
+
+        Subscription { queue }
+    }
+
+    /// Timestamps `update` and hands it to every subscriber
+    pub fn publish(&self, update: PlaybackUpdate) {
+        let event = PlaybackEvent {
+            time: SystemTime::now(),
+            update,
+        };
+
+        let mut subscribers = self.inner.subscribers.lock().unwrap();
+        // Dropped subscriptions are gone for good
+        subscribers.retain(|queue| {
+            queue.upgrade().is_some_and(|queue| {
+                queue.push(event.clone());
+                true
+            })
+        });
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.inner
+            .subscribers
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|queue| queue.strong_count() > 0)
+            .count()
+    }
+}
+
+/// Receives the events of an `EventBus`, independently of other subscriptions.
+/// Dropping it unsubscribes
+#[derive(Debug)]
+pub struct Subscription {
+    queue: Arc<Queue>,
+}
+
+impl Subscription {
+    /// Blocks until there is an event, fails once the bus is gone and every event was received
+    pub fn recv(&self) -> Result<PlaybackEvent, RecvError> {
+        let mut state = self.queue.state.lock().unwrap();
+        loop {
+            if let Some(event) = state.events.pop_front() {
+                return Ok(event);
+            }
+            if state.closed {
+                return Err(RecvError);
+            }
+            state = self.queue.ready.wait(state).unwrap();
+        }
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<PlaybackEvent, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.queue.state.lock().unwrap();
+        loop {
+            if let Some(event) = state.events.pop_front() {
+                return Ok(event);
+            }
+            if state.closed {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            state = self.queue.ready.wait_timeout(state, remaining).unwrap().0;
+        }
+    }
+
+    pub fn try_recv(&self) -> Result<PlaybackEvent, TryRecvError> {
+        let mut state = self.queue.state.lock().unwrap();
+        match state.events.pop_front() {
+            Some(event) => Ok(event),
+            None if state.closed => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// All events that are waiting, without blocking
+    pub fn try_iter(&self) -> impl Iterator<Item = PlaybackEvent> + '_ {
+        std::iter::from_fn(|| self.try_recv().ok())
+    }
+
+    /// How many events were dropped so far because this subscriber fell behind
+    pub fn dropped(&self) -> u64 {
+        self.queue.state.lock().unwrap().dropped
+    }
+
+    /// Stop receiving events, same as dropping the subscription
+    pub fn unsubscribe(self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::playback::AudioLevel;
+
+    fn level() -> PlaybackUpdate {
+        PlaybackUpdate::Level(AudioLevel {
+            rms: vec![-20.0],
+            peak: vec![-10.0],
+        })
+    }
+
+    #[test]
+    fn subscribers() {
+        let bus = EventBus::new();
+        let first = bus.subscribe();
+        let second = bus.subscribe();
+
+        bus.publish(PlaybackUpdate::Playing);
+        assert!(matches!(
+            first.try_recv().unwrap().update,
+            PlaybackUpdate::Playing
+        ));
+        assert!(matches!(
+            second.try_recv().unwrap().update,
+            PlaybackUpdate::Playing
+        ));
+
+        second.unsubscribe();
+        bus.publish(PlaybackUpdate::Stopped);
+        assert_eq!(bus.subscriber_count(), 1);
+        assert!(matches!(
+            first.try_recv().unwrap().update,
+            PlaybackUpdate::Stopped
+        ));
+        assert_eq!(first.try_recv().unwrap_err(), TryRecvError::Empty);
+
+        let publisher = bus.clone();
+        thread::spawn(move || publisher.publish(PlaybackUpdate::Loading));
+        assert!(matches!(
+            first.recv_timeout(Duration::from_secs(5)).unwrap().update,
+            PlaybackUpdate::Loading
+        ));
+
+        drop(bus);
+        assert_eq!(first.recv().unwrap_err(), RecvError);
+    }
+
+    #[test]
+    fn slow_subscriber() {
+        let bus = EventBus::new();
+        let slow = bus.subscribe_with_capacity(3);
+
+        bus.publish(PlaybackUpdate::Playing);
+        bus.publish(level());
+        bus.publish(PlaybackUpdate::Error("one".to_string()));
+        bus.publish(PlaybackUpdate::Error("two".to_string()));
+        bus.publish(PlaybackUpdate::Stopped);
+
+        // The level goes first, then the oldest event
+        let updates: Vec<_> = slow.try_iter().map(|event| event.update).collect();
+        assert_eq!(slow.dropped(), 2);
+        assert!(matches!(
+            updates.as_slice(),
+            [
+                PlaybackUpdate::Error(one),
+                PlaybackUpdate::Error(two),
+                PlaybackUpdate::Stopped
+            ] if one == "one" && two == "two"
+        ));
+    }
+}
@@ -0,0 +1,318 @@
+use std::{fmt::Display, time::Duration};
+
+use reqwest::{Url, blocking::Client};
+
+/// Codecs that mean a variant carries video
+const VIDEO_CODECS: [&str; 6] = ["avc1", "avc3", "hvc1", "hev1", "vp09", "av01"];
+
+/// Error type that is returned from HLS-related operations - Fetching and parsing playlists
+#[derive(Debug)]
+pub enum HlsError {
+    NetworkError(reqwest::Error),
+    InvalidUrl(String),
+    InvalidPlaylist(String),
+}
+
+impl Display for HlsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NetworkError(err) => write!(f, "{err}"),
+            Self::InvalidUrl(url) => write!(f, "Invalid URL: {url}"),
+            Self::InvalidPlaylist(msg) => write!(f, "Invalid HLS playlist: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for HlsError {}
+
+/// Which variant of an HLS stream to play, if it offers several
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VariantPolicy {
+    /// Let GStreamer switch between variants by the available bandwidth
+    Auto,
+    /// An audio-only variant or rendition if there is one, the lowest bitrate variant otherwise
+    #[default]
+    AudioOnly,
+    Lowest,
+    Highest,
+}
+
+/// An `EXT-X-STREAM-INF` entry of a master playlist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub uri: String,
+    /// Peak bitrate in bits per second
+    pub bandwidth: u64,
+    pub has_video: bool,
+}
+
+/// An `EXT-X-MEDIA` entry of a master playlist, e.g. an alternative audio track
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rendition {
+    pub uri: String,
+    pub is_default: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Playlist {
+    /// Lists the variants of a stream
+    Master {
+        variants: Vec<Variant>,
+        /// Audio renditions that have their own playlist
+        audio: Vec<Rendition>,
+    },
+    /// Lists the segments of a variant
+    Media {
+        target_duration: Option<Duration>,
+        segments: Vec<String>,
+    },
+}
+
+/// The stream that is actually played
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HlsSource {
+    pub uri: Url,
+    /// Longest segment duration, buffering should cover a few segments
+    pub target_duration: Option<Duration>,
+}
+
+/// Wether a stream URL points at an HLS playlist
+pub fn is_hls(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|url| url.path().to_ascii_lowercase().ends_with(".m3u8"))
+}
+
+pub fn fetch_playlist(client: &Client, url: &Url) -> Result<Playlist, HlsError> {
+    let text = client
+        .get(url.clone())
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.text())
+        .map_err(HlsError::NetworkError)?;
+
+    parse(&text).ok_or_else(|| HlsError::InvalidPlaylist(url.to_string()))
+}
+
+/// Joins a URI of a playlist to the URL of the playlist
+pub fn join(playlist: &Url, uri: &str) -> Result<Url, HlsError> {
+    playlist
+        .join(uri)
+        .map_err(|_| HlsError::InvalidUrl(uri.to_string()))
+}
+
+/// Picks the variant to play by `policy`, and reads how long its segments are
+pub fn resolve(client: &Client, url: &Url, policy: VariantPolicy) -> Result<HlsSource, HlsError> {
+    let (uri, media_uri, playlist) = match fetch_playlist(client, url)? {
+        master @ Playlist::Master { .. } => {
+            let media_uri = master
+                .select(policy)
+                .or_else(|| master.select(VariantPolicy::Lowest))
+                .ok_or_else(|| HlsError::InvalidPlaylist("No variants".to_string()))
+                .and_then(|uri| join(url, uri))?;
+
+            // The master playlist is kept when GStreamer picks the variants
+            let uri = if policy == VariantPolicy::Auto {
+                url.clone()
+            } else {
+                media_uri.clone()
+            };
+            let playlist = fetch_playlist(client, &media_uri)?;
+            (uri, media_uri, playlist)
+        }
+        media => (url.clone(), url.clone(), media),
+    };
+
+    match playlist {
+        Playlist::Media {
+            target_duration, ..
+        } => Ok(HlsSource {
+            uri,
+            target_duration,
+        }),
+        Playlist::Master { .. } => Err(HlsError::InvalidPlaylist(format!(
+            "Nested master playlist: {media_uri}"
+        ))),
+    }
+}
+
+/// `None` if `text` isn't an HLS playlist at all
+pub fn parse(text: &str) -> Option<Playlist> {
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    if lines.next()? != "#EXTM3U" {
+        return None;
+    }
+
+    let mut variants = vec![];
+    let mut audio = vec![];
+    let mut target_duration = None;
+    let mut segments = vec![];
+    // Attributes of the `EXT-X-STREAM-INF` the next URI belongs to
+    let mut stream_inf = None;
+
+    for line in lines {
+        if let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            stream_inf = Some(attributes);
+        } else if let Some(attributes) = line.strip_prefix("#EXT-X-MEDIA:") {
+            let attribute = |name| attribute(attributes, name);
+            if attribute("TYPE") == Some("AUDIO")
+                && let Some(uri) = attribute("URI")
+            {
+                audio.push(Rendition {
+                    uri: uri.to_string(),
+                    is_default: attribute("DEFAULT") == Some("YES"),
+                });
+            }
+        } else if let Some(duration) = line.strip_prefix("#EXT-X-TARGETDURATION:") {
+            target_duration = duration.parse().ok().map(Duration::from_secs);
+        } else if line.starts_with('#') {
+            continue;
+        } else if let Some(attributes) = stream_inf.take() {
+            let attribute = |name| attribute(attributes, name);
+            variants.push(Variant {
+                uri: line.to_string(),
+                bandwidth: attribute("BANDWIDTH")
+                    .and_then(|bandwidth| bandwidth.parse().ok())
+                    .unwrap_or(0),
+                has_video: attribute("RESOLUTION").is_some()
+                    || attribute("CODECS").is_some_and(|codecs| {
+                        codecs
+                            .split(',')
+                            .any(|codec| VIDEO_CODECS.iter().any(|v| codec.trim().starts_with(v)))
+                    }),
+            });
+        } else {
+            segments.push(line.to_string());
+        }
+    }
+
+    if variants.is_empty() {
+        Some(Playlist::Media {
+            target_duration,
+            segments,
+        })
+    } else {
+        Some(Playlist::Master { variants, audio })
+    }
+}
+
+/// Value of an attribute in an attribute list like `BANDWIDTH=64000,CODECS="mp4a.40.2"`
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attributes;
+
+    while !rest.is_empty() {
+        let (key, value) = rest.split_once('=')?;
+        let (value, next) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                let (value, next) = quoted.split_once('"')?;
+                (value, next.strip_prefix(',').unwrap_or(next))
+            }
+            None => value.split_once(',').unwrap_or((value, "")),
+        };
+
+        if key.trim() == name {
+            return Some(value);
+        }
+        rest = next;
+    }
+
+    None
+}
+
+impl Playlist {
+    /// URI of the variant or rendition to play, `None` for media playlists
+    pub fn select(&self, policy: VariantPolicy) -> Option<&str> {
+        let Self::Master { variants, audio } = self else {
+            return None;
+        };
+
+        let lowest = || variants.iter().min_by_key(|variant| variant.bandwidth);
+        let variant = match policy {
+            // GStreamer starts with the first variant
+            VariantPolicy::Auto => variants.first(),
+            VariantPolicy::Lowest => lowest(),
+            VariantPolicy::Highest => variants.iter().max_by_key(|variant| variant.bandwidth),
+            VariantPolicy::AudioOnly => {
+                let rendition = audio
+                    .iter()
+                    .find(|rendition| rendition.is_default)
+                    .or_else(|| audio.first());
+                if let Some(rendition) = rendition {
+                    return Some(&rendition.uri);
+                }
+
+                variants
+                    .iter()
+                    .filter(|variant| !variant.has_video)
+                    .max_by_key(|variant| variant.bandwidth)
+                    .or_else(lowest)
+            }
+        };
+
+        variant.map(|variant| variant.uri.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MASTER: &str = r#"#EXTM3U
+#EXT-X-STREAM-INF:BANDWIDTH=800000,RESOLUTION=640x360,CODECS="avc1.4d401e,mp4a.40.2"
+video/index.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=128000,CODECS="mp4a.40.2"
+audio_high/index.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=48000,CODECS="mp4a.40.5"
+audio_low/index.m3u8
+"#;
+
+    #[test]
+    fn variants() {
+        let master = parse(MASTER).unwrap();
+
+        assert_eq!(
+            master.select(VariantPolicy::AudioOnly),
+            Some("audio_high/index.m3u8")
+        );
+        assert_eq!(
+            master.select(VariantPolicy::Lowest),
+            Some("audio_low/index.m3u8")
+        );
+        assert_eq!(
+            master.select(VariantPolicy::Highest),
+            Some("video/index.m3u8")
+        );
+        assert_eq!(master.select(VariantPolicy::Auto), Some("video/index.m3u8"));
+    }
+
+    #[test]
+    fn audio_renditions() {
+        let master = parse(
+            r#"#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID="aac",NAME="English",DEFAULT=NO,URI="en.m3u8"
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID="aac",NAME="Deutsch, Original",DEFAULT=YES,URI="de.m3u8"
+#EXT-X-STREAM-INF:BANDWIDTH=800000,RESOLUTION=640x360,AUDIO="aac"
+video.m3u8
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(master.select(VariantPolicy::AudioOnly), Some("de.m3u8"));
+    }
+
+    #[test]
+    fn media_playlist() {
+        let media = parse(
+            "#EXTM3U\n#EXT-X-TARGETDURATION:10\n#EXTINF:9.9,\nseg1.aac\n#EXTINF:10,\nseg2.aac\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            media,
+            Playlist::Media {
+                target_duration: Some(Duration::from_secs(10)),
+                segments: vec!["seg1.aac".to_string(), "seg2.aac".to_string()],
+            }
+        );
+        assert_eq!(media.select(VariantPolicy::AudioOnly), None);
+        assert_eq!(parse("[playlist]\nFile1=http://radio.example"), None);
+    }
+}
@@ -1,4 +1,16 @@
 pub mod cache;
+pub mod charset;
+pub mod equalizer;
+pub mod events;
+pub mod hls;
+pub mod monitor;
+pub mod network;
+pub mod now_playing;
+pub mod output;
 pub mod playback;
+pub mod scrobble;
+pub mod session;
+mod silence;
 pub mod stations;
+pub mod title_parser;
 mod utils;
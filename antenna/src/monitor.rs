@@ -0,0 +1,445 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{
+        Arc,
+        mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use reqwest::blocking::Client;
+
+use crate::{
+    network::{NetworkError, NetworkSettings},
+    playback::TrackTags,
+    stations::Station,
+    title_parser::TitleParser,
+};
+
+/// How long to wait before connecting again once a stream broke off
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+/// Error type that is returned when a station can't be watched
+#[derive(Debug)]
+pub enum MonitorError {
+    NetworkError(reqwest::Error),
+    /// The stream doesn't send ICY metadata
+    NoMetadata(String),
+    ReadError(io::Error),
+}
+
+impl Display for MonitorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NetworkError(err) => write!(f, "{err}"),
+            Self::NoMetadata(url) => write!(f, "No ICY metadata: {url}"),
+            Self::ReadError(err) => write!(f, "Unable to read stream: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for MonitorError {}
+
+/// A new title on a watched station
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorEvent {
+    pub station_id: String,
+    pub tags: Arc<TrackTags>,
+}
+
+#[derive(Debug)]
+struct Watch {
+    /// Stops the thread once dropped
+    _stop: Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+/// Watches what stations are playing without playing them.
+///
+/// Every station gets a connection of its own that asks for ICY metadata.
+/// The audio in between is skipped without decoding it, so dozens of stations can be watched at once
+#[derive(Debug)]
+pub struct StationMonitor {
+    client: Client,
+    network: NetworkSettings,
+    sender: Sender<MonitorEvent>,
+    receiver: Receiver<MonitorEvent>,
+    watches: HashMap<String, Watch>,
+}
+
+impl StationMonitor {
+    pub fn new(network: &NetworkSettings) -> Result<Self, NetworkError> {
+        let (sender, receiver) = mpsc::channel();
+
+        Ok(Self {
+            client: network.client()?,
+            network: network.clone(),
+            sender,
+            receiver,
+            watches: HashMap::new(),
+        })
+    }
+
+    /// Start watching `station`, or start over if it is watched already.
+    /// Broken streams are reconnected, streams without metadata are given up on
+    pub fn watch(&mut self, station: &Station, parser: TitleParser) {
+        let (stop_sender, stop_receiver) = mpsc::channel();
+        let client = self.client.clone();
+        let network = self.network.clone();
+        let events = self.sender.clone();
+        let station_id = station.id.clone();
+        let url = station.url.clone();
+
+        let thread = thread::spawn(move || {
+            let mut current = String::new();
+
+            loop {
+                let result = read_titles(&client, &network, &url, &stop_receiver, |title| {
+                    if title == current {
+                        return;
+                    }
+                    current = title.to_string();
+
+                    let _ = events.send(MonitorEvent {
+                        station_id: station_id.clone(),
+                        tags: Arc::new(parser.parse(title, None)),
+                    });
+                });
+
+                if matches!(result, Err(MonitorError::NoMetadata(_))) {
+                    return;
+                }
+
+                // Connect again, unless the watch was stopped
+                if stop_receiver.recv_timeout(RECONNECT_DELAY) != Err(RecvTimeoutError::Timeout) {
+                    return;
+                }
+            }
+        });
+
+        self.watches.insert(
+            station.id.clone(),
+            Watch {
+                _stop: stop_sender,
+                thread,
+            },
+        );
+    }
+
+    /// Stop watching a station. Its thread quits after the next metadata block or read timeout
+    pub fn unwatch(&mut self, station_id: &str) {
+        self.watches.remove(station_id);
+    }
+
+    /// Wether `station_id` is watched and wasn't given up on
+    pub fn is_watching(&self, station_id: &str) -> bool {
+        self.watches
+            .get(station_id)
+            .is_some_and(|watch| !watch.thread.is_finished())
+    }
+
+    /// Ids of all watched stations
+    pub fn watched(&self) -> impl Iterator<Item = &str> {
+        self.watches.keys().map(String::as_str)
+    }
+
+    pub fn try_recv(&self) -> Result<MonitorEvent, TryRecvError> {
+        self.receiver.try_recv()
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<MonitorEvent, RecvTimeoutError> {
+        self.receiver.recv_timeout(timeout)
+    }
+}
+
+/// Calls `on_title` with every `StreamTitle` of the stream at `url`,
+/// until the stream ends or `stop` is signalled
+fn read_titles(
+    client: &Client,
+    network: &NetworkSettings,
+    url: &str,
+    stop: &Receiver<()>,
+    on_title: impl FnMut(&str),
+) -> Result<(), MonitorError> {
+    let result = client
+        .get(url)
+        .header("Icy-MetaData", "1")
+        .send()
+        .and_then(|response| response.error_for_status());
+
+    match result {
+        Ok(response) => {
+            let interval = response
+                .headers()
+                .get("icy-metaint")
+                .and_then(|interval| interval.to_str().ok());
+            let interval = metadata_interval(interval, url)?;
+            read_metadata(response, interval, stop, on_title)
+        }
+        // SHOUTcast v1 servers answer with `ICY 200 OK`, which isn't HTTP to reqwest.
+        // Those are asked again over a plain connection, which can't go through a proxy
+        Err(err)
+            if err.is_request()
+                && !err.is_connect()
+                && !err.is_timeout()
+                && network.proxy.is_none()
+                && url.starts_with("http://") =>
+        {
+            let (stream, interval) = icy_request(network, url)?;
+            read_metadata(stream, interval, stop, on_title)
+        }
+        Err(err) => Err(MonitorError::NetworkError(err)),
+    }
+}
+
+/// The parsed `icy-metaint` header, streams without it don't send metadata
+fn metadata_interval(header: Option<&str>, url: &str) -> Result<u64, MonitorError> {
+    header
+        .and_then(|interval| interval.trim().parse::<u64>().ok())
+        .filter(|&interval| interval > 0)
+        .ok_or_else(|| MonitorError::NoMetadata(url.to_string()))
+}
+
+/// Requests the stream at `url` over a plain TCP connection, accepting an `ICY` status line.
+/// Returns the stream after the headers, and its metadata interval
+fn icy_request(
+    network: &NetworkSettings,
+    url: &str,
+) -> Result<(BufReader<TcpStream>, u64), MonitorError> {
+    let invalid = |msg: String| MonitorError::ReadError(io::Error::other(msg));
+
+    let parsed = reqwest::Url::parse(url).map_err(|err| invalid(err.to_string()))?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| invalid(format!("No host in {url}")))?;
+    let address = (host, parsed.port_or_known_default().unwrap_or(80))
+        .to_socket_addrs()
+        .map_err(MonitorError::ReadError)?
+        .next()
+        .ok_or_else(|| invalid(format!("Unable to resolve {host}")))?;
+
+    let stream = TcpStream::connect_timeout(&address, network.connect_timeout)
+        .map_err(MonitorError::ReadError)?;
+    stream
+        .set_read_timeout(Some(network.timeout))
+        .and_then(|()| stream.set_write_timeout(Some(network.timeout)))
+        .map_err(MonitorError::ReadError)?;
+
+    let path = match parsed.query() {
+        Some(query) => format!("{}?{query}", parsed.path()),
+        None => parsed.path().to_string(),
+    };
+    let mut request = format!(
+        "GET {path} HTTP/1.0\r\nHost: {host}\r\nUser-Agent: {}\r\nIcy-MetaData: 1\r\n",
+        network.user_agent
+    );
+    for (name, value) in &network.headers {
+        request.push_str(&format!("{name}: {value}\r\n"));
+    }
+    request.push_str("\r\n");
+    (&stream)
+        .write_all(request.as_bytes())
+        .map_err(MonitorError::ReadError)?;
+
+    // Header values like `icy-name` are often Latin-1, so lines are read as bytes
+    let mut reader = BufReader::new(stream);
+    let mut read_line = || {
+        let mut line = vec![];
+        reader
+            .read_until(b'\n', &mut line)
+            .map_err(MonitorError::ReadError)?;
+        Ok::<_, MonitorError>(String::from_utf8_lossy(&line).trim_end().to_string())
+    };
+
+    let status = read_line()?;
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(invalid(format!("Unexpected response: {status}")));
+    }
+
+    let mut interval = None;
+    loop {
+        let line = read_line()?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.trim().eq_ignore_ascii_case("icy-metaint")
+        {
+            interval = Some(value.to_string());
+        }
+    }
+
+    let interval = metadata_interval(interval.as_deref(), url)?;
+    Ok((reader, interval))
+}
+
+/// Skips the audio of a stream with ICY metadata every `interval` bytes, passing its titles to `on_title`
+fn read_metadata(
+    mut stream: impl Read,
+    interval: u64,
+    stop: &Receiver<()>,
+    mut on_title: impl FnMut(&str),
+) -> Result<(), MonitorError> {
+    let mut metadata = vec![];
+    while stop.try_recv() == Err(TryRecvError::Empty) {
+        // The audio is thrown away as it arrives
+        let skipped = io::copy(&mut (&mut stream).take(interval), &mut io::sink())
+            .map_err(MonitorError::ReadError)?;
+        if skipped < interval {
+            return Ok(());
+        }
+
+        // The length byte counts in blocks of 16 bytes
+        let mut length = [0];
+        stream
+            .read_exact(&mut length)
+            .map_err(MonitorError::ReadError)?;
+        metadata.resize(usize::from(length[0]) * 16, 0);
+        stream
+            .read_exact(&mut metadata)
+            .map_err(MonitorError::ReadError)?;
+
+        if let Some(title) = stream_title(&metadata) {
+            on_title(&title);
+        }
+    }
+
+    Ok(())
+}
+
+/// The `StreamTitle` of a metadata block like `StreamTitle='Artist - Title';StreamUrl='';`.
+/// It is read as Latin-1, the `TitleParser` repairs other charsets
+fn stream_title(metadata: &[u8]) -> Option<String> {
+    let text: String = metadata
+        .iter()
+        .take_while(|&&byte| byte != 0)
+        .map(|&byte| char::from(byte))
+        .collect();
+
+    let start = text.find("StreamTitle='")? + "StreamTitle='".len();
+    let rest = &text[start..];
+    // Titles may contain quotes themselves
+    let title = rest
+        .find("';")
+        .map_or(rest.trim_end_matches('\''), |end| &rest[..end]);
+
+    (!title.trim().is_empty()).then(|| title.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    const METAINT: usize = 32;
+
+    fn metadata_block(title: &str) -> Vec<u8> {
+        let text = format!("StreamTitle='{title}';");
+        let blocks = text.len().div_ceil(16);
+        let mut block = vec![u8::try_from(blocks).unwrap()];
+        block.extend(text.as_bytes());
+        block.resize(1 + blocks * 16, 0);
+        block
+    }
+
+    /// Serves a stream that sends every title of `titles` twice, then an empty block, then ends.
+    /// Every connection gets the stream, answered with the `status` line
+    fn fake_icy_server(status: &'static str, titles: &[&str]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/stream", listener.local_addr().unwrap());
+
+        let mut body = vec![];
+        for title in titles {
+            for _ in 0..2 {
+                body.extend([0xff; METAINT]);
+                body.extend(metadata_block(title));
+            }
+        }
+        body.extend([0xff; METAINT]);
+        body.push(0);
+        body.extend([0xff; METAINT / 2]);
+
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                // Drain the request
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request);
+
+                let _ = write!(
+                    stream,
+                    "{status}\r\nContent-Type: audio/mpeg\r\nicy-metaint: {METAINT}\r\nConnection: close\r\n\r\n"
+                );
+                let _ = stream.write_all(&body);
+            }
+        });
+
+        url
+    }
+
+    #[test]
+    fn watches_titles() {
+        let url = fake_icy_server(
+            "HTTP/1.0 200 OK",
+            &["Daft Punk - One More Time", "Björk - Jóga"],
+        );
+        let station = Station {
+            url,
+            ..Station::mock("fake", "Local")
+        };
+
+        let mut monitor = StationMonitor::new(&NetworkSettings::default()).unwrap();
+        monitor.watch(&station, TitleParser::default());
+
+        let timeout = Duration::from_secs(5);
+        let first = monitor.recv_timeout(timeout).unwrap();
+        assert_eq!(first.station_id, "fake");
+        assert_eq!(first.tags.artist.as_deref(), Some("Daft Punk"));
+        assert_eq!(first.tags.title, "One More Time");
+
+        // Latin-1 bytes of the UTF-8 title are repaired
+        let second = monitor.recv_timeout(timeout).unwrap();
+        assert_eq!(second.tags.artist.as_deref(), Some("Björk"));
+        assert_eq!(second.tags.title, "Jóga");
+
+        // Repeated titles aren't sent again
+        assert_eq!(
+            monitor.recv_timeout(Duration::from_millis(200)),
+            Err(RecvTimeoutError::Timeout)
+        );
+        assert_eq!(monitor.watched().collect::<Vec<_>>(), ["fake"]);
+
+        monitor.unwatch("fake");
+        assert!(!monitor.is_watching("fake"));
+    }
+
+    #[test]
+    fn watches_shoutcast_v1() {
+        let url = fake_icy_server("ICY 200 OK", &["Air - La Femme d'Argent"]);
+        let station = Station {
+            url,
+            ..Station::mock("shoutcast", "Local")
+        };
+
+        let mut monitor = StationMonitor::new(&NetworkSettings::default()).unwrap();
+        monitor.watch(&station, TitleParser::default());
+
+        let event = monitor.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(event.tags.artist.as_deref(), Some("Air"));
+        assert_eq!(event.tags.title, "La Femme d'Argent");
+    }
+
+    #[test]
+    fn stream_titles() {
+        assert_eq!(
+            stream_title(b"StreamTitle='Guns N' Roses - Don't Cry';StreamUrl='';\0\0"),
+            Some("Guns N' Roses - Don't Cry".to_string())
+        );
+        assert_eq!(stream_title(b"StreamTitle='';\0"), None);
+        assert_eq!(stream_title(b"StreamUrl='http://radio.example';"), None);
+    }
+}
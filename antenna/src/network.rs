@@ -0,0 +1,209 @@
+use std::{fmt::Display, fs, io, path::PathBuf, time::Duration};
+
+use gstreamer::glib::object::ObjectExt;
+use reqwest::{
+    Certificate, Proxy,
+    blocking::Client,
+    header::{HeaderMap, HeaderName, HeaderValue},
+};
+
+/// User-Agent that is sent if none is configured
+pub const DEFAULT_USER_AGENT: &str = concat!("antenna/", env!("CARGO_PKG_VERSION"));
+
+/// Error type that is returned when network settings can't be applied -
+/// An invalid proxy or header, or an unreadable CA bundle
+#[derive(Debug)]
+pub enum NetworkError {
+    ClientError(reqwest::Error),
+    InvalidHeader(String),
+    CaBundleError(io::Error),
+}
+
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ClientError(err) => write!(f, "Unable to create HTTP client: {err}"),
+            Self::InvalidHeader(header) => write!(f, "Invalid HTTP header: {header}"),
+            Self::CaBundleError(err) => write!(f, "Unable to read CA bundle: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+/// How to reach the network, shared by the HTTP requests of antenna
+/// (station list, now-playing, HLS playlists) and the streams themselves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkSettings {
+    /// e.g. `http://proxy:3128`. `None` uses the `http_proxy` environment variables
+    pub proxy: Option<String>,
+    pub user_agent: String,
+    /// Sent with every request, as name and value
+    pub headers: Vec<(String, String)>,
+    pub connect_timeout: Duration,
+    /// How long a single read or write may take
+    pub timeout: Duration,
+    /// PEM file with additional certificates to trust
+    pub ca_bundle: Option<PathBuf>,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            headers: vec![],
+            connect_timeout: Duration::from_secs(10),
+            timeout: Duration::from_secs(15),
+            ca_bundle: None,
+        }
+    }
+}
+
+impl NetworkSettings {
+    /// Creates an HTTP client that uses these settings
+    pub fn client(&self) -> Result<Client, NetworkError> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let invalid = || NetworkError::InvalidHeader(format!("{name}: {value}"));
+            headers.append(
+                HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid())?,
+                HeaderValue::from_str(value).map_err(|_| invalid())?,
+            );
+        }
+
+        let mut builder = Client::builder()
+            .user_agent(&self.user_agent)
+            .default_headers(headers)
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.timeout);
+
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(Proxy::all(proxy).map_err(NetworkError::ClientError)?);
+        }
+
+        if let Some(path) = &self.ca_bundle {
+            let pem = fs::read(path).map_err(NetworkError::CaBundleError)?;
+            for certificate in
+                Certificate::from_pem_bundle(&pem).map_err(NetworkError::ClientError)?
+            {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        builder.build().map_err(NetworkError::ClientError)
+    }
+
+    /// Applies these settings to the source element uridecodebin created for a stream.
+    /// Only HTTP sources (souphttpsrc) have these properties, others are left alone
+    pub(crate) fn setup_source(&self, source: &gstreamer::Element) {
+        let has = |name| source.find_property(name).is_some();
+
+        if has("user-agent") {
+            source.set_property("user-agent", &self.user_agent);
+        }
+
+        if let Some(proxy) = &self.proxy
+            && has("proxy")
+        {
+            source.set_property("proxy", proxy);
+        }
+
+        if !self.headers.is_empty() && has("extra-headers") {
+            let headers = self
+                .headers
+                .iter()
+                .fold(
+                    gstreamer::Structure::builder("extra-headers"),
+                    |headers, (name, value)| headers.field(name.as_str(), value),
+                )
+                .build();
+            source.set_property("extra-headers", headers);
+        }
+
+        if has("timeout") {
+            let secs = u32::try_from(self.timeout.as_secs()).unwrap_or(u32::MAX);
+            source.set_property("timeout", secs);
+        }
+
+        if let Some(path) = &self.ca_bundle
+            && let Some(path) = path.to_str()
+            && has("ssl-ca-file")
+        {
+            source.set_property("ssl-ca-file", path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::*;
+
+    #[test]
+    fn client_sends_headers() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+
+        // Answers with the headers of the request
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = String::new();
+            let mut reader = BufReader::new(&stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                request.push_str(&line.to_lowercase());
+                line.clear();
+            }
+
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{request}",
+                request.len()
+            );
+        });
+
+        let settings = NetworkSettings {
+            user_agent: "tester/1.0".to_string(),
+            headers: vec![("X-Station".to_string(), "jazz".to_string())],
+            ..Default::default()
+        };
+        let request = settings
+            .client()
+            .unwrap()
+            .get(url)
+            .send()
+            .unwrap()
+            .text()
+            .unwrap();
+
+        assert!(request.contains("user-agent: tester/1.0"));
+        assert!(request.contains("x-station: jazz"));
+    }
+
+    #[test]
+    fn invalid_settings() {
+        let header = NetworkSettings {
+            headers: vec![("Bad Name".to_string(), "value".to_string())],
+            ..Default::default()
+        };
+        assert!(matches!(
+            header.client(),
+            Err(NetworkError::InvalidHeader(_))
+        ));
+
+        let ca_bundle = NetworkSettings {
+            ca_bundle: Some("/nonexistent/ca.pem".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            ca_bundle.client(),
+            Err(NetworkError::CaBundleError(_))
+        ));
+    }
+}
@@ -0,0 +1,477 @@
+use std::fmt::Display;
+
+use reqwest::{Url, blocking::Client, header::RANGE};
+use serde_json::Value;
+
+use crate::hls::{self, HlsError, Playlist, VariantPolicy};
+
+/// How much of an HLS segment is searched for an ID3 tag
+const SEGMENT_PREFIX: u64 = 64 * 1024;
+
+/// Error type that is returned from now-playing providers - The request failing, or an unexpected response
+#[derive(Debug)]
+pub enum NowPlayingError {
+    NetworkError(reqwest::Error),
+    InvalidUrl(String),
+    /// The response didn't look like what the provider expects
+    InvalidResponse(String),
+    HlsError(HlsError),
+}
+
+impl Display for NowPlayingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NetworkError(err) => write!(f, "{err}"),
+            Self::InvalidUrl(url) => write!(f, "Invalid URL: {url}"),
+            Self::InvalidResponse(msg) => write!(f, "Invalid response: {msg}"),
+            Self::HlsError(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for NowPlayingError {}
+
+/// A raw title, as it would have come in a tag of the stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawTitle {
+    pub title: String,
+    pub artist: Option<String>,
+}
+
+/// Asks some other place than the stream itself what is playing.
+/// Used for stations that don't send tags with their audio
+pub trait NowPlayingProvider: Send {
+    /// `None` if nothing is known to be playing
+    fn fetch(&self, client: &Client) -> Result<Option<RawTitle>, NowPlayingError>;
+}
+
+/// Picks the providers that could work for a stream URL:
+/// ID3 tags for HLS streams, Icecast or Shoutcast status pages for everything else
+pub fn discover(stream_url: &str) -> Result<Box<dyn NowPlayingProvider>, NowPlayingError> {
+    let url = parse_url(stream_url)?;
+
+    if hls::is_hls(stream_url) {
+        return Ok(Box::new(HlsId3 { playlist: url }));
+    }
+
+    Ok(Box::new(FirstWorking(vec![
+        Box::new(IcecastStatus::new(&url)?),
+        Box::new(ShoutcastCurrentSong::new(&url)?),
+    ])))
+}
+
+fn parse_url(url: &str) -> Result<Url, NowPlayingError> {
+    Url::parse(url).map_err(|_| NowPlayingError::InvalidUrl(url.to_string()))
+}
+
+fn get(client: &Client, url: Url) -> Result<reqwest::blocking::Response, NowPlayingError> {
+    client
+        .get(url)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .map_err(NowPlayingError::NetworkError)
+}
+
+/// Tries providers in order, the first one that answers is used
+pub struct FirstWorking(pub Vec<Box<dyn NowPlayingProvider>>);
+
+impl NowPlayingProvider for FirstWorking {
+    fn fetch(&self, client: &Client) -> Result<Option<RawTitle>, NowPlayingError> {
+        let mut last_err = None;
+        for provider in &self.0 {
+            match provider.fetch(client) {
+                Ok(title) => return Ok(title),
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        last_err.map_or(Ok(None), Err)
+    }
+}
+
+/// The `status-json.xsl` page of an Icecast server
+pub struct IcecastStatus {
+    status_url: Url,
+    /// Path of the stream on the server, e.g. `/radio.mp3`
+    mount: String,
+}
+
+impl IcecastStatus {
+    pub fn new(stream_url: &Url) -> Result<Self, NowPlayingError> {
+        let status_url = stream_url
+            .join("/status-json.xsl")
+            .map_err(|_| NowPlayingError::InvalidUrl(stream_url.to_string()))?;
+
+        Ok(Self {
+            status_url,
+            mount: stream_url.path().to_string(),
+        })
+    }
+}
+
+impl NowPlayingProvider for IcecastStatus {
+    fn fetch(&self, client: &Client) -> Result<Option<RawTitle>, NowPlayingError> {
+        let body = get(client, self.status_url.clone())?
+            .bytes()
+            .map_err(NowPlayingError::NetworkError)?;
+        let status: Value = serde_json::from_slice(&body)
+            .map_err(|err| NowPlayingError::InvalidResponse(err.to_string()))?;
+
+        // A single source is an object, multiple ones an array
+        let sources = match &status["icestats"]["source"] {
+            Value::Array(sources) => sources.iter().collect(),
+            Value::Object(_) => vec![&status["icestats"]["source"]],
+            _ => return Err(NowPlayingError::InvalidResponse("No sources".to_string())),
+        };
+
+        let source = sources
+            .iter()
+            .find(|source| {
+                source["listenurl"]
+                    .as_str()
+                    .and_then(|url| Url::parse(url).ok())
+                    .is_some_and(|url| url.path() == self.mount)
+            })
+            .or_else(|| sources.first().filter(|_| sources.len() == 1));
+
+        let Some(title) = source.and_then(|source| source["title"].as_str()) else {
+            return Ok(None);
+        };
+
+        Ok(Some(RawTitle {
+            title: title.to_string(),
+            artist: source
+                .and_then(|source| source["artist"].as_str())
+                .map(str::to_string),
+        }))
+    }
+}
+
+/// The `currentsong` page of a Shoutcast v2 server
+pub struct ShoutcastCurrentSong {
+    url: Url,
+}
+
+impl ShoutcastCurrentSong {
+    pub fn new(stream_url: &Url) -> Result<Self, NowPlayingError> {
+        let mut url = stream_url
+            .join("/currentsong")
+            .map_err(|_| NowPlayingError::InvalidUrl(stream_url.to_string()))?;
+
+        // Streams are usually at `/;` or `/stream/<sid>/`
+        let sid = stream_url
+            .query_pairs()
+            .find(|(key, _)| key == "sid")
+            .map_or_else(|| "1".to_string(), |(_, sid)| sid.into_owned());
+        url.query_pairs_mut().append_pair("sid", &sid);
+
+        Ok(Self { url })
+    }
+}
+
+impl NowPlayingProvider for ShoutcastCurrentSong {
+    fn fetch(&self, client: &Client) -> Result<Option<RawTitle>, NowPlayingError> {
+        let response = get(client, self.url.clone())?;
+
+        // Shoutcast answers everything with its status page, which is HTML
+        if response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.contains("html"))
+        {
+            return Err(NowPlayingError::InvalidResponse(
+                "Not a Shoutcast v2 server".to_string(),
+            ));
+        }
+
+        let title = response.text().map_err(NowPlayingError::NetworkError)?;
+        let title = title.trim();
+
+        Ok((!title.is_empty()).then(|| RawTitle {
+            title: title.to_string(),
+            artist: None,
+        }))
+    }
+}
+
+/// ID3 tags at the start of the newest segment of an HLS stream
+pub struct HlsId3 {
+    playlist: Url,
+}
+
+impl HlsId3 {
+    pub const fn new(playlist: Url) -> Self {
+        Self { playlist }
+    }
+
+    /// URL of the newest media segment, following a master playlist to its first variant
+    fn newest_segment(&self, client: &Client) -> Result<Url, NowPlayingError> {
+        let mut playlist = self.playlist.clone();
+
+        // Master playlists only point at other playlists
+        for _ in 0..2 {
+            let next = match hls::fetch_playlist(client, &playlist) {
+                Ok(master @ Playlist::Master { .. }) => master
+                    .select(VariantPolicy::Auto)
+                    .map(|uri| hls::join(&playlist, uri)),
+                Ok(Playlist::Media { segments, .. }) => {
+                    let segment = segments.last().ok_or_else(|| {
+                        NowPlayingError::InvalidResponse("Empty playlist".to_string())
+                    })?;
+                    return hls::join(&playlist, segment).map_err(NowPlayingError::HlsError);
+                }
+                Err(err) => Some(Err(err)),
+            };
+
+            playlist = next
+                .ok_or_else(|| NowPlayingError::InvalidResponse("No variants".to_string()))?
+                .map_err(NowPlayingError::HlsError)?;
+        }
+
+        Err(NowPlayingError::InvalidResponse(
+            "Nested master playlists".to_string(),
+        ))
+    }
+}
+
+impl NowPlayingProvider for HlsId3 {
+    fn fetch(&self, client: &Client) -> Result<Option<RawTitle>, NowPlayingError> {
+        let segment = self.newest_segment(client)?;
+        let data = client
+            .get(segment)
+            .header(RANGE, format!("bytes=0-{}", SEGMENT_PREFIX - 1))
+            .send()
+            .and_then(|response| response.error_for_status())
+            .and_then(|response| response.bytes())
+            .map_err(NowPlayingError::NetworkError)?;
+
+        Ok(find_id3(&data).and_then(parse_id3))
+    }
+}
+
+/// The first ID3v2 tag somewhere in `data`
+fn find_id3(data: &[u8]) -> Option<&[u8]> {
+    let start = data.windows(3).position(|window| window == b"ID3")?;
+    Some(&data[start..])
+}
+
+/// Reads the title and artist frames of an ID3v2.3 or ID3v2.4 tag
+fn parse_id3(tag: &[u8]) -> Option<RawTitle> {
+    let syncsafe = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .fold(0usize, |size, byte| (size << 7) | usize::from(byte & 0x7f))
+    };
+
+    let version = *tag.get(3)?;
+    if tag.len() < 10 || !(3..=4).contains(&version) {
+        return None;
+    }
+    let end = (10 + syncsafe(&tag[6..10])).min(tag.len());
+
+    let mut title = None;
+    let mut artist = None;
+    let mut pos = 10;
+
+    while pos + 10 <= end {
+        let id = &tag[pos..pos + 4];
+        let size = if version == 4 {
+            syncsafe(&tag[pos + 4..pos + 8])
+        } else {
+            tag[pos + 4..pos + 8]
+                .iter()
+                .fold(0, |size, byte| (size << 8) | usize::from(*byte))
+        };
+        // Padding
+        if id[0] == 0 || size == 0 {
+            break;
+        }
+
+        let body = tag.get(pos + 10..(pos + 10 + size).min(end))?;
+        match id {
+            b"TIT2" => title = decode_text_frame(body),
+            b"TPE1" => artist = decode_text_frame(body),
+            _ => {}
+        }
+
+        pos += 10 + size;
+    }
+
+    Some(RawTitle {
+        title: title.filter(|title| !title.is_empty())?,
+        artist: artist.filter(|artist| !artist.is_empty()),
+    })
+}
+
+fn decode_text_frame(body: &[u8]) -> Option<String> {
+    let (encoding, text) = body.split_first()?;
+
+    let text = match encoding {
+        // Latin-1
+        0 => text.iter().map(|&byte| char::from(byte)).collect(),
+        // UTF-16 with a byte order mark, and UTF-16BE without
+        1 | 2 => {
+            let (little_endian, text) = match text {
+                [0xff, 0xfe, rest @ ..] => (true, rest),
+                [0xfe, 0xff, rest @ ..] => (false, rest),
+                _ => (false, text),
+            };
+            let units = text.chunks_exact(2).map(|pair| {
+                if little_endian {
+                    u16::from_le_bytes([pair[0], pair[1]])
+                } else {
+                    u16::from_be_bytes([pair[0], pair[1]])
+                }
+            });
+            char::decode_utf16(units)
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect()
+        }
+        3 => String::from_utf8_lossy(text).into_owned(),
+        _ => return None,
+    };
+
+    Some(text.trim_end_matches('\0').trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::*;
+    use crate::network::NetworkSettings;
+
+    /// Serves canned responses by path on a local port, returns the base URL
+    fn serve(routes: Vec<(&'static str, &'static str, Vec<u8>)>) -> Url {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+
+                let mut request_line = String::new();
+                let mut reader = BufReader::new(&stream);
+                let _ = reader.read_line(&mut request_line);
+                // Skip the headers
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                    line.clear();
+                }
+
+                let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+                let response = match routes.iter().find(|(path, _, _)| *path == target) {
+                    Some((_, content_type, body)) => {
+                        let mut response = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                            body.len()
+                        )
+                        .into_bytes();
+                        response.extend_from_slice(body);
+                        response
+                    }
+                    None => {
+                        b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_vec()
+                    }
+                };
+                let _ = stream.write_all(&response);
+            }
+        });
+
+        Url::parse(&base).unwrap()
+    }
+
+    fn title(title: &str, artist: Option<&str>) -> Option<RawTitle> {
+        Some(RawTitle {
+            title: title.to_string(),
+            artist: artist.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn icecast() {
+        let status = r#"{"icestats": {"source": [
+            {"listenurl": "http://localhost:8000/other", "title": "Wrong"},
+            {"listenurl": "http://localhost:8000/radio.mp3", "title": "Artist - Song"}
+        ]}}"#;
+        let base = serve(vec![(
+            "/status-json.xsl",
+            "application/json",
+            status.into(),
+        )]);
+
+        let provider = discover(base.join("/radio.mp3").unwrap().as_str()).unwrap();
+        assert_eq!(
+            provider
+                .fetch(&NetworkSettings::default().client().unwrap())
+                .unwrap(),
+            title("Artist - Song", None)
+        );
+    }
+
+    #[test]
+    fn shoutcast() {
+        let base = serve(vec![(
+            "/currentsong?sid=1",
+            "text/plain",
+            "Artist - Song\n".into(),
+        )]);
+
+        // Not an Icecast server, so the Shoutcast page is used
+        let provider = discover(base.join("/;").unwrap().as_str()).unwrap();
+        assert_eq!(
+            provider
+                .fetch(&NetworkSettings::default().client().unwrap())
+                .unwrap(),
+            title("Artist - Song", None)
+        );
+    }
+
+    #[test]
+    fn hls_id3() {
+        fn frame(id: &[u8], text: &str) -> Vec<u8> {
+            let mut frame = id.to_vec();
+            frame.extend_from_slice(&(text.len() as u32 + 1).to_be_bytes());
+            frame.extend_from_slice(&[0, 0, 3]);
+            frame.extend_from_slice(text.as_bytes());
+            frame
+        }
+
+        let frames = [frame(b"TIT2", "Sóng"), frame(b"TPE1", "Ártist")].concat();
+        let mut segment = b"\xff\xf1garbage".to_vec();
+        segment.extend_from_slice(b"ID3\x03\x00\x00");
+        segment.extend_from_slice(&[0, 0, 0, frames.len() as u8]);
+        segment.extend_from_slice(&frames);
+        segment.extend_from_slice(&[0; 16]);
+
+        let base = serve(vec![
+            (
+                "/live/master.m3u8",
+                "application/vnd.apple.mpegurl",
+                "#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=64000\nlow/index.m3u8\n".into(),
+            ),
+            (
+                "/live/low/index.m3u8",
+                "application/vnd.apple.mpegurl",
+                "#EXTM3U\n#EXTINF:6,\nseg1.aac\n#EXTINF:6,\nseg2.aac\n".into(),
+            ),
+            ("/live/low/seg2.aac", "audio/aac", segment),
+        ]);
+
+        let provider = discover(base.join("/live/master.m3u8").unwrap().as_str()).unwrap();
+        assert_eq!(
+            provider
+                .fetch(&NetworkSettings::default().client().unwrap())
+                .unwrap(),
+            title("Sóng", Some("Ártist"))
+        );
+    }
+}
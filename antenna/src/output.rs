@@ -0,0 +1,287 @@
+use std::path::PathBuf;
+
+use gstreamer::glib::object::{Cast, ObjectExt};
+use gstreamer::prelude::{DeviceExt, DeviceMonitorExt, DeviceMonitorExtManual, ElementExt};
+use gstreamer::prelude::{GstBinExt, GstObjectExt};
+
+use crate::playback::PlaybackError;
+
+/// Where the audio of the playback branch ends up
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum AudioSink {
+    /// Let GStreamer pick the best available sink
+    #[default]
+    Auto,
+    /// PulseAudio, optionally on a specific device
+    Pulse { device: Option<String> },
+    /// PipeWire, optionally on a specific target object
+    Pipewire { device: Option<String> },
+    /// ALSA on the given device (e.g. `default`, `hw:0,0`)
+    Alsa { device: String },
+    /// Write the decoded audio into a WAV file
+    File(PathBuf),
+    /// Discard all audio. Useful for headless use and tests
+    Fake,
+}
+
+impl AudioSink {
+    /// Creates the GStreamer element for this sink.
+    pub(crate) fn build(&self) -> Result<gstreamer::Element, PlaybackError> {
+        let element = match self {
+            Self::Auto => make("autoaudiosink")?,
+            Self::Pulse { device } => {
+                let sink = make("pulsesink")?;
+                if let Some(device) = device {
+                    sink.set_property("device", device);
+                }
+                sink
+            }
+            Self::Pipewire { device } => {
+                let sink = make("pipewiresink")?;
+                if let Some(device) = device {
+                    sink.set_property("target-object", device);
+                }
+                sink
+            }
+            Self::Alsa { device } => {
+                let sink = make("alsasink")?;
+                sink.set_property("device", device);
+                sink
+            }
+            Self::File(path) => {
+                let location = path
+                    .to_str()
+                    .ok_or_else(|| PlaybackError::InvalidPath(path.clone()))?;
+
+                let bin = gstreamer::parse::bin_from_description(
+                    "wavenc ! filesink name=filesink async=false",
+                    true,
+                )
+                .map_err(PlaybackError::PipelineCreation)?;
+
+                bin.by_name("filesink")
+                    .ok_or(PlaybackError::MissingElement("filesink"))?
+                    .set_property("location", location);
+
+                bin.upcast()
+            }
+            Self::Fake => {
+                // Sync to the clock, so the pipeline still runs in real time
+                let sink = make("fakesink")?;
+                sink.set_property("sync", true);
+                sink
+            }
+        };
+
+        Ok(element)
+    }
+}
+
+fn make(factory: &str) -> Result<gstreamer::Element, PlaybackError> {
+    gstreamer::ElementFactory::make(factory)
+        .build()
+        .map_err(PlaybackError::ElementCreation)
+}
+
+/// An audio output device that was found on the system
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputDevice {
+    /// Human readable name of the device
+    pub name: String,
+    /// Sink that plays to this device
+    pub sink: AudioSink,
+}
+
+/// Lists all audio output devices GStreamer knows about.
+/// Devices of unsupported sink types are skipped.
+/// NOTE: You must call `PlaybackManager::init` before calling this function
+pub fn list_output_devices() -> Result<Vec<OutputDevice>, PlaybackError> {
+    let monitor = gstreamer::DeviceMonitor::new();
+    monitor.add_filter(Some("Audio/Sink"), None);
+    monitor.start().map_err(PlaybackError::ElementCreation)?;
+
+    let devices = monitor
+        .devices()
+        .into_iter()
+        .filter_map(|device| {
+            let element = device.create_element(None).ok()?;
+            let device_property = |name: &str| {
+                element
+                    .has_property(name)
+                    .then(|| element.property::<Option<String>>(name))
+                    .flatten()
+            };
+
+            let sink = match element.factory()?.name().as_str() {
+                "pulsesink" => AudioSink::Pulse {
+                    device: device_property("device"),
+                },
+                "pipewiresink" => AudioSink::Pipewire {
+                    device: device_property("target-object"),
+                },
+                "alsasink" => AudioSink::Alsa {
+                    device: device_property("device")?,
+                },
+                _ => return None,
+            };
+
+            Some(OutputDevice {
+                name: device.display_name().to_string(),
+                sink,
+            })
+        })
+        .collect();
+
+    monitor.stop();
+    Ok(devices)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{
+        env, fs,
+        path::Path,
+        process,
+        time::{Duration, Instant},
+    };
+
+    use super::*;
+    use crate::{
+        events::Subscription,
+        playback::{PlaybackManager, PlaybackOptions, PlaybackUpdate},
+    };
+
+    const SAMPLE_RATE: u32 = 44_100;
+
+    /// Elements the pipeline and song recordings are built from
+    const REQUIRED_ELEMENTS: [&str; 10] = [
+        "uridecodebin",
+        "wavparse",
+        "audioconvert",
+        "volume",
+        "equalizer-10bands",
+        "level",
+        "wavenc",
+        "vorbisenc",
+        "oggmux",
+        "fakesink",
+    ];
+
+    /// Initializes GStreamer, or returns `false` if the plugins the pipeline needs aren't installed
+    pub(crate) fn init_playback() -> bool {
+        if PlaybackManager::init().is_err() {
+            eprintln!("Skipped: GStreamer can't be initialized");
+            return false;
+        }
+
+        let missing: Vec<_> = REQUIRED_ELEMENTS
+            .into_iter()
+            .filter(|name| gstreamer::ElementFactory::find(name).is_none())
+            .collect();
+        if !missing.is_empty() {
+            eprintln!("Skipped: missing GStreamer elements {missing:?}");
+        }
+        missing.is_empty()
+    }
+
+    /// Writes a mono 16 bit WAV file with a 440 Hz tone, returning its URI
+    pub(crate) fn tone_file(path: &Path, length: Duration) -> String {
+        let samples = (f64::from(SAMPLE_RATE) * length.as_secs_f64()) as u32;
+        let mut data = Vec::with_capacity(44 + samples as usize * 2);
+        data.extend(b"RIFF");
+        data.extend((36 + samples * 2).to_le_bytes());
+        data.extend(b"WAVEfmt ");
+        data.extend(16u32.to_le_bytes());
+        data.extend(1u16.to_le_bytes()); // PCM
+        data.extend(1u16.to_le_bytes()); // Mono
+        data.extend(SAMPLE_RATE.to_le_bytes());
+        data.extend((SAMPLE_RATE * 2).to_le_bytes());
+        data.extend(2u16.to_le_bytes());
+        data.extend(16u16.to_le_bytes());
+        data.extend(b"data");
+        data.extend((samples * 2).to_le_bytes());
+        for i in 0..samples {
+            let phase = f64::from(i) * 440.0 * std::f64::consts::TAU / f64::from(SAMPLE_RATE);
+            data.extend(((phase.sin() * 8000.0) as i16).to_le_bytes());
+        }
+
+        fs::write(path, data).unwrap();
+        reqwest::Url::from_file_path(path).unwrap().to_string()
+    }
+
+    /// Waits for an update `predicate` is true for, returns whether one arrived in time
+    pub(crate) fn wait_for(
+        updates: &Subscription,
+        predicate: impl Fn(&PlaybackUpdate) -> bool,
+    ) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+            match updates.recv_timeout(timeout) {
+                Ok(event) if predicate(&event.update) => return true,
+                Ok(_) => {}
+                Err(_) => return false,
+            }
+        }
+        false
+    }
+
+    /// Level updates arrive as long as audio is flowing
+    pub(crate) fn is_level(update: &PlaybackUpdate) -> bool {
+        matches!(update, PlaybackUpdate::Level(_))
+    }
+
+    #[test]
+    fn fake_and_file_sinks() {
+        if !init_playback() {
+            return;
+        }
+
+        let dir = env::temp_dir();
+        let source = dir.join(format!("antenna-output-{}.wav", process::id()));
+        let output = dir.join(format!("antenna-output-{}-file.wav", process::id()));
+        let uri = tone_file(&source, Duration::from_secs(10));
+
+        let mut manager = PlaybackManager::new(&PlaybackOptions {
+            sink: AudioSink::Fake,
+            ..Default::default()
+        })
+        .unwrap();
+        let updates = manager.subscribe();
+        manager.set_source_uri(&uri).unwrap();
+        manager.play().unwrap();
+        assert!(
+            wait_for(&updates, is_level),
+            "Nothing played on the fake sink"
+        );
+
+        manager.set_sink(&AudioSink::File(output.clone())).unwrap();
+        assert!(
+            wait_for(&updates, is_level),
+            "Nothing played on the file sink"
+        );
+
+        // Switching away finishes the file
+        manager.set_sink(&AudioSink::Fake).unwrap();
+        assert!(
+            wait_for(&updates, is_level),
+            "Nothing played after switching back"
+        );
+
+        // The sizes in the header were written once the file was complete
+        let data = fs::read(&output).unwrap();
+        let size = |offset: usize| {
+            u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as usize
+        };
+        assert_eq!(size(4), data.len() - 8);
+        let mut chunk = 12;
+        while &data[chunk..chunk + 4] != b"data" {
+            chunk += 8 + size(chunk + 4);
+        }
+        assert!(size(chunk + 4) > 0);
+        assert_eq!(size(chunk + 4), data.len() - chunk - 8);
+
+        drop(manager);
+        fs::remove_file(source).unwrap();
+        fs::remove_file(output).unwrap();
+    }
+}
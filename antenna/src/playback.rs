@@ -1,16 +1,29 @@
 // The vast majority of this code is from "Shortwave"
 // --> https://gitlab.gnome.org/World/Shortwave/
 
-use std::path::Path;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender, TryRecvError};
 use std::sync::{Arc, Mutex};
-use std::thread;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 use gstreamer::glib::object::ObjectExt;
 use gstreamer::glib::{self, clone};
-use gstreamer::prelude::{ElementExtManual, GstObjectExt, PadExtManual};
+use gstreamer::prelude::{ElementExtManual, GstObjectExt, PadExtManual, TocSetterExt};
 use gstreamer::{MessageView, PadProbeType};
+
+use crate::equalizer::{self, Gains};
+use crate::events::{EventBus, Subscription};
+use crate::hls::{self, VariantPolicy};
+use crate::network::NetworkSettings;
+use crate::now_playing::NowPlayingProvider;
+use crate::output::AudioSink;
+use crate::session::{CueSheet, SessionRecording};
+use crate::silence::SilenceGate;
+use crate::title_parser::TitleParser;
 use gstreamer::{
     format::UnsignedIntoSigned,
     glib::object::Cast,
@@ -23,6 +36,130 @@ pub struct TrackTags {
     pub artist: Option<String>,
 }
 
+impl TrackTags {
+    #[cfg(any(test, feature = "mock"))]
+    pub fn mock(title: &str, artist: Option<&str>) -> Self {
+        Self {
+            title: title.to_string(),
+            artist: artist.map(str::to_string),
+        }
+    }
+}
+
+impl Display for TrackTags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(artist) = &self.artist {
+            write!(f, "{artist} - {}", self.title)
+        } else {
+            write!(f, "{}", self.title)
+        }
+    }
+}
+
+/// Technical information about the stream that is currently playing.
+/// Fields are `None` until GStreamer tells us about them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamInfo {
+    pub codec: Option<String>,
+    /// Bitrate in bits per second
+    pub bitrate: Option<u32>,
+    /// Sample rate in Hz
+    pub sample_rate: Option<i32>,
+    pub channels: Option<i32>,
+}
+
+/// Audio levels of the playback in dB, one value per channel
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AudioLevel {
+    pub rms: Vec<f64>,
+    pub peak: Vec<f64>,
+}
+
+/// How long the bus thread waits for a message before checking if it should stop
+const BUS_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How long `shutdown` waits for a recording to be written completely
+const FINALIZE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often level and spectrum updates are sent (in nanoseconds)
+const ANALYSIS_INTERVAL: u64 = 100_000_000;
+/// Amount of frequency bands in `PlaybackUpdate::Spectrum`
+pub const SPECTRUM_BANDS: u32 = 32;
+/// Magnitude (dB) of silent bands in `PlaybackUpdate::Spectrum`
+pub const SPECTRUM_THRESHOLD: i32 = -80;
+
+/// Silence that is cut from the start and the end of song recordings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SilenceTrim {
+    /// Audio below this level (dB) counts as silence
+    pub threshold: i32,
+    /// Shorter silences at the start or the end are kept. Pauses within a song always are
+    pub min_duration: Duration,
+}
+
+/// How much of a stream is buffered before and while playing.
+/// More buffering survives worse connections, less starts playing sooner and stays closer to live
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferSettings {
+    pub duration: Duration,
+    /// Maximum amount of buffered bytes, `None` for GStreamer's default
+    pub size: Option<u32>,
+    /// HLS is delivered in segments, this many are buffered even if they're longer than `duration`
+    pub hls_segments: u32,
+}
+
+impl BufferSettings {
+    pub const DEFAULT: Self = Self {
+        duration: Duration::from_secs(6),
+        size: None,
+        hls_segments: 3,
+    };
+
+    /// Start quickly and stay close to the live edge, at the cost of more stalls
+    pub const LOW_LATENCY: Self = Self {
+        duration: Duration::from_secs(1),
+        size: Some(256 * 1024),
+        hls_segments: 1,
+    };
+
+    /// Ride out connection drops, e.g. on mobile networks
+    pub const FLAKY_NETWORK: Self = Self {
+        duration: Duration::from_secs(30),
+        size: Some(8 * 1024 * 1024),
+        hls_segments: 5,
+    };
+
+    /// How much of a stream is buffered, given the target duration of its segments if it's HLS
+    fn duration_for(&self, segment_duration: Option<Duration>) -> Duration {
+        segment_duration.map_or(self.duration, |segment| {
+            (segment * self.hls_segments).max(self.duration)
+        })
+    }
+}
+
+impl Default for BufferSettings {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Options for building the pipeline of a `PlaybackManager`
+#[derive(Debug, Clone, Default)]
+pub struct PlaybackOptions {
+    /// Where the audio ends up
+    pub sink: AudioSink,
+    /// Wether to analyze the frequency spectrum of the playback, see `PlaybackUpdate::Spectrum`
+    pub spectrum: bool,
+    /// Trim leading and trailing silence of song recordings (not session recordings)
+    pub silence_trim: Option<SilenceTrim>,
+    /// Which variant of HLS streams to play
+    pub hls_variant: VariantPolicy,
+    pub buffer: BufferSettings,
+    pub network: NetworkSettings,
+    /// Only record, without playing anything. There is no audio sink, volume, equalizer or spectrum,
+    /// so a station can be recorded in the background while another one is played.
+    /// The level is still measured, to tell songs apart from silence
+    pub record_only: bool,
+}
+
 /// BufferingState ensures that the player acutally pauses when buffered
 /// And can return back to playing normally when buffering
 /// is done
@@ -33,6 +170,28 @@ struct BufferingState {
     is_live: Option<bool>,
 }
 
+/// The title that is currently playing, and the session recording following it
+#[derive(Default, Debug)]
+struct TitleState {
+    /// The raw title, used to only send `NewSong` if it really changed
+    current: String,
+    /// The artist the stream sent along with the current title, if any
+    artist: Option<String>,
+    /// Wether the stream sends titles itself, now-playing providers are only asked if it doesn't
+    inline: bool,
+    parser: TitleParser,
+    session: Option<SessionRecording>,
+}
+
+/// The source that was set last, the variant of HLS sources is picked in the background
+#[derive(Default, Debug)]
+struct SourceState {
+    /// Counts the sources that were set, so a resolution that finishes too late is dropped
+    generation: u64,
+    /// Wether the playlists of an HLS source are still being read, playback starts once they are
+    resolving: bool,
+}
+
 impl BufferingState {
     fn reset(&mut self) {
         self.buffering = false;
@@ -43,13 +202,63 @@ impl BufferingState {
     }
 }
 
+/// Error type that is returned from playback-related operations - Building the pipeline,
+/// changing its state, attaching a recorder and such..
+#[derive(Debug)]
+pub enum PlaybackError {
+    /// GStreamer itself could not be initialized
+    Init(glib::Error),
+    /// A pipeline or bin description could not be parsed, usually because a plugin is missing
+    PipelineCreation(glib::Error),
+    /// An element (or pad / bus) we rely on could not be found
+    MissingElement(&'static str),
+    /// An element could not be created, usually because a plugin is missing
+    ElementCreation(glib::BoolError),
+    /// GStreamer refused to change the state of the pipeline or the recorderbin
+    StateChange(gstreamer::StateChangeError),
+    /// Two pads could not be linked together
+    Link(gstreamer::PadLinkError),
+    /// Adding or removing an element from a bin failed
+    Bin(glib::BoolError),
+    /// The pipeline is not running, so there is nothing to record
+    NotPlaying,
+    /// The recording path is not valid UTF-8
+    InvalidPath(PathBuf),
+}
+
+impl Display for PlaybackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Init(err) => write!(f, "Unable to initialize GStreamer: {err}"),
+            Self::PipelineCreation(err) => write!(f, "Unable to create pipeline: {err}"),
+            Self::MissingElement(name) => write!(f, "Missing pipeline element: {name}"),
+            Self::ElementCreation(err) => write!(f, "Unable to create element: {err}"),
+            Self::StateChange(err) => write!(f, "Unable to change pipeline state: {err}"),
+            Self::Link(err) => write!(f, "Unable to link pads: {err}"),
+            Self::Bin(err) => write!(f, "{err}"),
+            Self::NotPlaying => write!(f, "Nothing is playing"),
+            Self::InvalidPath(path) => write!(f, "Invalid recording path: {}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for PlaybackError {}
+
 #[derive(Debug, Clone)]
 pub enum PlaybackUpdate {
     Playing,
     Stopped,
     NewSong(Arc<TrackTags>),
+    StreamInfo(StreamInfo),
+    /// Sent about 10 times per second while playing
+    Level(AudioLevel),
+    /// Magnitudes (dB) of `SPECTRUM_BANDS` frequency bands from low to high.
+    /// Only sent if `PlaybackOptions::spectrum` is set
+    Spectrum(Vec<f32>),
     Error(String),
     Loading,
+    /// Percentage of the buffer that is filled, playback waits until it reaches 100
+    Buffering(u8),
 }
 
 /// Manages the playback and recording
@@ -57,9 +266,23 @@ pub enum PlaybackUpdate {
 #[derive(Debug)]
 pub struct PlaybackManager {
     pipeline: gstreamer::Pipeline,
+    /// `None` for record-only pipelines
+    audiosink: Option<gstreamer::Element>,
     recorderbin: Option<gstreamer::Bin>,
-    current_title: Arc<Mutex<String>>,
-    sender: Sender<PlaybackUpdate>,
+    silence_trim: Option<SilenceTrim>,
+    hls_variant: VariantPolicy,
+    buffer: BufferSettings,
+    network: NetworkSettings,
+    title_state: Arc<Mutex<TitleState>>,
+    stream_info: Arc<Mutex<StreamInfo>>,
+    source: Arc<Mutex<SourceState>>,
+    events: EventBus,
+    /// Dropping this stops the now-playing poller
+    now_playing: Option<Sender<()>>,
+    /// Reads the messages of the pipeline, see `setup_signals`
+    bus_thread: Option<JoinHandle<()>>,
+    /// Tells the bus thread to stop
+    is_shut_down: Arc<AtomicBool>,
 
     buffering_state: Arc<Mutex<BufferingState>>,
     is_playing: Arc<AtomicBool>,
@@ -67,99 +290,435 @@ pub struct PlaybackManager {
 
 impl PlaybackManager {
     /// Initialize GStreamer --> Only call this once!!!
-    pub fn init() {
-        gstreamer::init().unwrap();
+    pub fn init() -> Result<(), PlaybackError> {
+        gstreamer::init().map_err(PlaybackError::Init)
     }
 
-    /// Creates a new instance of PlaybackManager.
+    /// Creates a new instance of PlaybackManager, see `PlaybackManager::subscribe` for its updates.
     /// NOTE: You must call `PlaybackManager::init` before calling this function
-    pub fn new(sender: Sender<PlaybackUpdate>) -> Self {
-        // create gstreamer pipeline
-        let pipeline_description = "uridecodebin name=uridecodebin use-buffering=true buffer-duration=6000000000 ! audioconvert name=audioconvert ! tee name=tee ! queue ! autoaudiosink name=autoaudiosink".to_string();
+    pub fn new(options: &PlaybackOptions) -> Result<Self, PlaybackError> {
+        // create gstreamer pipeline, the audio sink is attached below.
+        // Volume, equalizing and analysis only happen on the playback branch, so recordings are not affected by them
+        let spectrum = if options.spectrum {
+            format!(
+                "! spectrum bands={SPECTRUM_BANDS} threshold={SPECTRUM_THRESHOLD} interval={ANALYSIS_INTERVAL} post-messages=true message-magnitude=true "
+            )
+        } else {
+            String::new()
+        };
+        let buffer_duration = options.buffer.duration.as_nanos();
+        let buffer_size = options.buffer.size.map_or(-1, i64::from);
+        let branches = if options.record_only {
+            // The tee only feeds recorders and the level, nothing is played
+            format!(
+                "tee name=tee ! queue ! level interval={ANALYSIS_INTERVAL} post-messages=true ! fakesink async=false"
+            )
+        } else {
+            format!(
+                "tee name=tee ! queue ! volume name=volume ! equalizer-10bands name=equalizer ! level interval={ANALYSIS_INTERVAL} post-messages=true {spectrum}! audioconvert name=sinkconvert"
+            )
+        };
+        let pipeline_description = format!(
+            "uridecodebin name=uridecodebin use-buffering=true buffer-duration={buffer_duration} buffer-size={buffer_size} ! audioconvert name=audioconvert ! {branches}"
+        );
 
-        let pipeline =
-            gstreamer::parse::launch(&pipeline_description).expect("Unable to create pipeline");
-        let pipeline = pipeline.downcast::<gstreamer::Pipeline>().unwrap();
+        let pipeline = gstreamer::parse::launch(&pipeline_description)
+            .map_err(PlaybackError::PipelineCreation)?
+            .downcast::<gstreamer::Pipeline>()
+            .map_err(|_| PlaybackError::MissingElement("pipeline"))?;
         pipeline.set_message_forward(true); // <-- Forwards all messages from child nodes into the parent bin
 
         let buffering_state = Arc::new(Mutex::new(BufferingState::default()));
 
+        let audiosink = if options.record_only {
+            None
+        } else {
+            let audiosink = options.sink.build()?;
+            Self::link_audiosink(&pipeline, &audiosink)?;
+            Some(audiosink)
+        };
+
         let mut mgr = Self {
             recorderbin: None,
+            silence_trim: options.silence_trim,
+            hls_variant: options.hls_variant,
+            buffer: options.buffer,
+            network: options.network.clone(),
             pipeline,
-            sender,
+            audiosink,
+            events: EventBus::new(),
+            now_playing: None,
+            bus_thread: None,
+            is_shut_down: Arc::new(AtomicBool::from(false)),
             buffering_state,
-            current_title: Arc::new(Mutex::new(String::new())),
+            title_state: Arc::new(Mutex::new(TitleState::default())),
+            stream_info: Arc::new(Mutex::new(StreamInfo::default())),
+            source: Arc::new(Mutex::new(SourceState::default())),
             is_playing: Arc::new(AtomicBool::from(false)),
         };
 
-        mgr.setup_signals();
-        mgr
+        mgr.setup_signals()?;
+        Ok(mgr)
+    }
+
+    /// Receive the updates of this manager, until the subscription is dropped
+    pub fn subscribe(&self) -> Subscription {
+        self.events.subscribe()
+    }
+
+    /// The bus updates are published on, e.g. to subscribe with a different capacity
+    pub const fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Get an element of the pipeline by its name
+    fn element(&self, name: &'static str) -> Result<gstreamer::Element, PlaybackError> {
+        self.pipeline
+            .by_name(name)
+            .ok_or(PlaybackError::MissingElement(name))
+    }
+
+    /// Adds the audio sink to the pipeline and links it to the end of the playback branch
+    fn link_audiosink(
+        pipeline: &gstreamer::Pipeline,
+        audiosink: &gstreamer::Element,
+    ) -> Result<(), PlaybackError> {
+        let sinkconvert = pipeline
+            .by_name("sinkconvert")
+            .ok_or(PlaybackError::MissingElement("sinkconvert"))?;
+
+        pipeline.add(audiosink).map_err(PlaybackError::Bin)?;
+        sinkconvert.link(audiosink).map_err(PlaybackError::Bin)
+    }
+
+    /// Switch to another audio sink.
+    /// If a stream is playing it is restarted on the new sink, any recording is discarded.
+    pub fn set_sink(&mut self, sink: &AudioSink) -> Result<(), PlaybackError> {
+        // Record-only pipelines have no playback branch to attach it to
+        let sinkconvert = self.element("sinkconvert")?;
+        let audiosink = sink.build()?;
+        let was_playing = self.is_playing.load(Ordering::SeqCst);
+
+        let blocked = self
+            .audiosink
+            .as_ref()
+            .and_then(|old| Self::finish_audiosink(&sinkconvert, old));
+        self.stop_recording(true);
+        self.stop();
+        if let Some((srcpad, probe_id)) = blocked {
+            srcpad.remove_probe(probe_id);
+        }
+
+        // The old sink is only removed once the new one is linked,
+        // so a failure leaves the pipeline with a working playback branch
+        self.pipeline.add(&audiosink).map_err(PlaybackError::Bin)?;
+        if let Some(old) = &self.audiosink {
+            sinkconvert.unlink(old);
+        }
+        if let Err(err) = sinkconvert.link(&audiosink) {
+            let _ = self.pipeline.remove(&audiosink);
+            if let Some(old) = &self.audiosink {
+                let _ = sinkconvert.link(old);
+            }
+            return Err(PlaybackError::Bin(err));
+        }
+
+        if let Some(old) = self.audiosink.replace(audiosink) {
+            let _ = old.set_state(gstreamer::State::Null);
+            let _ = self.pipeline.remove(&old);
+        }
+
+        if was_playing {
+            self.play()?;
+        }
+
+        Ok(())
+    }
+
+    /// Send the end-of-stream through a sink that writes a file, so it can finish it
+    /// (e.g. the sizes in a WAV header), and wait until it reached the filesink.
+    /// Returns the probe that keeps further data out of the sink, it has to be removed
+    /// once the pipeline is stopped
+    fn finish_audiosink(
+        sinkconvert: &gstreamer::Element,
+        audiosink: &gstreamer::Element,
+    ) -> Option<(gstreamer::Pad, gstreamer::PadProbeId)> {
+        let filesink_pad = audiosink
+            .downcast_ref::<gstreamer::Bin>()?
+            .by_name("filesink")?
+            .static_pad("sink")?;
+        let audiosink_pad = audiosink.static_pad("sink")?;
+        let srcpad = sinkconvert.static_pad("src")?;
+
+        let (done_sender, done_receiver) = mpsc::channel();
+        filesink_pad.add_probe(PadProbeType::EVENT_DOWNSTREAM, move |_, info| {
+            let Some(gstreamer::PadProbeData::Event(event)) = &info.data else {
+                return gstreamer::PadProbeReturn::Ok;
+            };
+            if event.type_() != gstreamer::EventType::Eos {
+                return gstreamer::PadProbeReturn::Ok;
+            }
+
+            let _ = done_sender.send(());
+            gstreamer::PadProbeReturn::Remove
+        });
+
+        // No buffer may follow the end-of-stream, the sink would fail on it
+        let probe_id = srcpad.add_probe(PadProbeType::BLOCK_DOWNSTREAM, |_, _| {
+            gstreamer::PadProbeReturn::Ok
+        })?;
+
+        // Fails if the pipeline isn't running, there is nothing to finish then
+        if audiosink_pad.send_event(gstreamer::event::Eos::new()) {
+            let _ = done_receiver.recv_timeout(FINALIZE_TIMEOUT);
+        }
+
+        Some((srcpad, probe_id))
+    }
+
+    /// Set the volume of the playback, 1.0 being the original volume.
+    /// Recordings are not affected
+    pub fn set_volume(&self, volume: f64) -> Result<(), PlaybackError> {
+        self.element("volume")?
+            .set_property("volume", volume.clamp(0.0, 10.0));
+        Ok(())
+    }
+
+    /// The current volume of the playback
+    pub fn volume(&self) -> Result<f64, PlaybackError> {
+        Ok(self.element("volume")?.property::<f64>("volume"))
+    }
+
+    /// Set the gain (dB) of every band of the equalizer
+    pub fn set_equalizer(&self, gains: &Gains) -> Result<(), PlaybackError> {
+        for (band, gain) in gains.iter().enumerate() {
+            self.set_band_gain(band, *gain)?;
+        }
+
+        Ok(())
+    }
+
+    /// Set the gain (dB) of a single band of the equalizer.
+    /// The gain is clamped to the range the equalizer supports
+    pub fn set_band_gain(&self, band: usize, gain: f64) -> Result<(), PlaybackError> {
+        if band < equalizer::BANDS {
+            self.element("equalizer")?.set_property(
+                &format!("band{band}"),
+                gain.clamp(equalizer::MIN_GAIN, equalizer::MAX_GAIN),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// The current gain (dB) of every band of the equalizer
+    pub fn equalizer(&self) -> Result<Gains, PlaybackError> {
+        let equalizer = self.element("equalizer")?;
+        Ok(std::array::from_fn(|band| {
+            equalizer.property::<f64>(&format!("band{band}"))
+        }))
     }
 
     /// Main setup function, links the audio pads and sets up the
     /// Event-listening thread.
-    fn setup_signals(&mut self) {
+    fn setup_signals(&mut self) -> Result<(), PlaybackError> {
         // dynamically link uridecodebin element with audioconvert element
-        let uridecodebin = self.pipeline.by_name("uridecodebin").unwrap();
-        let audioconvert = self.pipeline.by_name("audioconvert").unwrap();
+        let uridecodebin = self.element("uridecodebin")?;
+        let audioconvert = self.element("audioconvert")?;
+        let events_clone = self.events.clone();
+        let stream_info_clone = self.stream_info.clone();
+
+        // Proxy, headers and such for HTTP sources
+        let network = self.network.clone();
+        uridecodebin.connect("source-setup", false, move |values| {
+            if let Some(source) = values
+                .get(1)
+                .and_then(|source| source.get::<gstreamer::Element>().ok())
+            {
+                network.setup_source(&source);
+            }
+            None
+        });
+
         uridecodebin.connect_pad_added(clone!(
             #[weak]
             audioconvert,
             move |_, src_pad| {
-                let sink_pad = audioconvert
-                    .static_pad("sink")
-                    .expect("Failed to get static sink pad from audioconvert");
+                let Some(sink_pad) = audioconvert.static_pad("sink") else {
+                    return;
+                };
                 if sink_pad.is_linked() {
                     return; // We are already linked. Ignoring.
                 }
 
-                let new_pad_caps = src_pad
-                    .current_caps()
-                    .expect("Failed to get caps of new pad.");
-                let new_pad_struct = new_pad_caps
-                    .structure(0)
-                    .expect("Failed to get first structure of caps.");
+                let Some(new_pad_caps) = src_pad.current_caps() else {
+                    return;
+                };
+                let Some(new_pad_struct) = new_pad_caps.structure(0) else {
+                    return;
+                };
                 let new_pad_type = new_pad_struct.name();
 
                 if new_pad_type.starts_with("audio/x-raw") {
                     // check if new_pad is audio
                     let _ = src_pad.link(&sink_pad);
+
+                    // The negotiated caps of the decoded audio
+                    Self::update_stream_info(&stream_info_clone, &events_clone, |info| {
+                        info.sample_rate = new_pad_struct.get("rate").ok();
+                        info.channels = new_pad_struct.get("channels").ok();
+                    });
                 }
             }
         ));
 
         // listen for new pipeline / bus messages
-        let bus = self.pipeline.bus().expect("Unable to get pipeline bus");
+        let bus = self
+            .pipeline
+            .bus()
+            .ok_or(PlaybackError::MissingElement("bus"))?;
 
         let pipeline_clone = self.pipeline.clone();
-        let sender_clone = self.sender.clone();
+        let events_clone = self.events.clone();
         let buffering_state_clone = self.buffering_state.clone();
-        let current_title_clone = self.current_title.clone();
+        let title_state_clone = self.title_state.clone();
+        let stream_info_clone = self.stream_info.clone();
         let is_playing = self.is_playing.clone();
+        let is_shut_down = self.is_shut_down.clone();
+
+        // Messages posted while the pipeline is stopped are dropped, so instead of waiting
+        // for a message to stop the thread, it checks the flag every now and then
+        self.bus_thread = Some(thread::spawn(move || {
+            let timeout = gstreamer::ClockTime::from_nseconds(BUS_POLL_INTERVAL.as_nanos() as u64);
+            while !is_shut_down.load(Ordering::SeqCst) {
+                let Some(message) = bus.timed_pop(timeout) else {
+                    continue;
+                };
 
-        thread::spawn(move || {
-            for message in bus.iter_timed(gstreamer::ClockTime::NONE) {
                 Self::parse_bus_message(
                     pipeline_clone.clone(),
                     &message,
                     &buffering_state_clone,
-                    sender_clone.clone(),
-                    current_title_clone.clone(),
+                    events_clone.clone(),
+                    title_state_clone.clone(),
+                    &stream_info_clone,
                     is_playing.clone(),
                 );
             }
-        });
+        }));
+
+        Ok(())
+    }
+
+    /// Finish any recording, stop all threads and tear down the pipeline.
+    /// Happens on drop as well, the manager can't be used afterwards
+    pub fn shutdown(&mut self) {
+        // Keep whatever was recorded, waiting until it is completely written
+        if self.is_recording() {
+            let (done_sender, done_receiver) = mpsc::channel();
+            self.detach_recorder(false, Some(done_sender));
+            let _ = done_receiver.recv_timeout(FINALIZE_TIMEOUT);
+        }
+
+        self.now_playing = None;
+        {
+            // The playlists of an HLS stream that are still being read are dropped
+            let mut source = self.source.lock().unwrap();
+            source.generation += 1;
+            self.is_playing.store(false, Ordering::SeqCst);
+        }
+        let _ = self.pipeline.set_state(gstreamer::State::Null);
+        self.buffering_state.lock().unwrap().reset();
+
+        // There is no bus thread if setting up the signals failed, or it was shut down already
+        self.is_shut_down.store(true, Ordering::SeqCst);
+        if let Some(bus_thread) = self.bus_thread.take() {
+            let _ = bus_thread.join();
+        }
     }
 
     /// Update the corrently playing station (URI)
-    /// Use this method if you want to switch a station
-    pub fn set_source_uri(&mut self, source: &str) {
+    /// Use this method if you want to switch a station.
+    /// The playlists of HLS streams are read in the background, `play` waits for them
+    pub fn set_source_uri(&mut self, source: &str) -> Result<(), PlaybackError> {
         let _ = self.pipeline.set_state(gstreamer::State::Null);
-        *self.current_title.lock().unwrap() = String::new();
+        self.now_playing = None;
+        {
+            let mut title_state = self.title_state.lock().unwrap();
+            title_state.current = String::new();
+            title_state.inline = false;
+        }
+        *self.stream_info.lock().unwrap() = StreamInfo::default();
+
+        let uridecodebin = self.element("uridecodebin")?;
+        let mut state = self.source.lock().unwrap();
+        state.generation += 1;
+        state.resolving = hls::is_hls(source);
+
+        if !state.resolving {
+            Self::apply_source(&uridecodebin, source, self.buffer.duration_for(None));
+            return Ok(());
+        }
+
+        // Reading the playlists can take a while, the caller isn't kept waiting for it
+        let generation = state.generation;
+        let source = source.to_string();
+        let network = self.network.clone();
+        let policy = self.hls_variant;
+        let buffer = self.buffer;
+        let source_state = self.source.clone();
+        let pipeline = self.pipeline.clone();
+        let buffering_state = self.buffering_state.clone();
+        let events = self.events.clone();
+        let is_playing = self.is_playing.clone();
+
+        thread::spawn(move || {
+            let (uri, segment_duration) = Self::resolve_hls(&network, &source, policy);
+
+            let mut state = source_state.lock().unwrap();
+            if state.generation != generation {
+                return; // Another source was set in the meantime
+            }
+            state.resolving = false;
+            Self::apply_source(&uridecodebin, &uri, buffer.duration_for(segment_duration));
+
+            // `play` was called while the playlists were read
+            if is_playing.load(Ordering::SeqCst)
+                && let Err(err) =
+                    Self::change_state(&pipeline, &buffering_state, gstreamer::State::Playing)
+            {
+                is_playing.store(false, Ordering::SeqCst);
+                events.publish(PlaybackUpdate::Error(err.to_string()));
+            }
+        });
 
-        let uridecodebin = self.pipeline.by_name("uridecodebin").unwrap();
-        uridecodebin.set_property("uri", source);
+        Ok(())
+    }
+
+    fn apply_source(uridecodebin: &gstreamer::Element, uri: &str, buffer_duration: Duration) {
+        uridecodebin.set_property("uri", uri);
+        uridecodebin.set_property(
+            "buffer-duration",
+            i64::try_from(buffer_duration.as_nanos()).unwrap_or(i64::MAX),
+        );
+    }
+
+    /// The variant of an HLS stream to play, and how long its segments are.
+    /// If the playlist can't be read, GStreamer is left to deal with the stream
+    fn resolve_hls(
+        network: &NetworkSettings,
+        source: &str,
+        policy: VariantPolicy,
+    ) -> (String, Option<Duration>) {
+        let resolved = reqwest::Url::parse(source).ok().and_then(|url| {
+            let client = network.client().ok()?;
+            hls::resolve(&client, &url, policy).ok()
+        });
+
+        match resolved {
+            Some(resolved) => (resolved.uri.to_string(), resolved.target_duration),
+            None => (source.to_string(), None),
+        }
     }
 
     /// Decide what to do with an incoming message
@@ -168,33 +727,42 @@ impl PlaybackManager {
         pipeline: gstreamer::Pipeline,
         message: &gstreamer::Message,
         buffering_state: &Arc<Mutex<BufferingState>>,
-        sender: Sender<PlaybackUpdate>,
-        current_title: Arc<Mutex<String>>,
+        events: EventBus,
+        title_state: Arc<Mutex<TitleState>>,
+        stream_info: &Arc<Mutex<StreamInfo>>,
         is_playing: Arc<AtomicBool>,
     ) {
         match message.view() {
             // Title changes
             MessageView::Tag(tag) => {
-                if let Some(t) = tag.tags().get::<gstreamer::tags::Title>() {
-                    let new_title = t.get().to_string();
-                    let mut track_tags = TrackTags {
-                        title: new_title,
-                        artist: None,
-                    };
-
-                    // only send message if title really changed.
-                    let mut current_title_locked = current_title.lock().unwrap();
-
-                    if let Some(t) = tag.tags().get::<gstreamer::tags::Artist>() {
-                        track_tags.artist = Some(t.get().to_string());
+                let tags = tag.tags();
+                Self::update_stream_info(stream_info, &events, |info| {
+                    if let Some(codec) = tags.get::<gstreamer::tags::AudioCodec>() {
+                        info.codec = Some(codec.get().to_string());
                     }
 
-                    if *current_title_locked != track_tags.title {
-                        current_title_locked.clone_from(&track_tags.title);
-                        sender
-                            .send(PlaybackUpdate::NewSong(Arc::new(track_tags)))
-                            .unwrap();
+                    // Prefer the nominal bitrate, the actual bitrate of VBR streams jumps around
+                    if let Some(bitrate) = tags.get::<gstreamer::tags::NominalBitrate>() {
+                        info.bitrate = Some(bitrate.get());
+                    } else if info.bitrate.is_none()
+                        && let Some(bitrate) = tags.get::<gstreamer::tags::Bitrate>()
+                    {
+                        info.bitrate = Some(bitrate.get());
                     }
+                });
+
+                if let Some(t) = tag.tags().get::<gstreamer::tags::Title>() {
+                    let artist = tag.tags().get::<gstreamer::tags::Artist>();
+
+                    let mut title_state = title_state.lock().unwrap();
+                    title_state.inline = true;
+                    Self::new_title(
+                        &pipeline,
+                        &mut title_state,
+                        &events,
+                        t.get(),
+                        artist.as_ref().map(|a| a.get()),
+                    );
                 }
             }
             MessageView::Buffering(buffering) => {
@@ -202,24 +770,28 @@ impl PlaybackManager {
 
                 // Wait until buffering is complete before start/resume playing
                 let mut buffering_state = buffering_state.lock().unwrap();
+                if percent < 100 || buffering_state.buffering {
+                    events.publish(PlaybackUpdate::Buffering(percent.clamp(0, 100) as u8));
+                }
+
                 if percent < 100 {
                     if !buffering_state.buffering {
                         buffering_state.buffering = true;
-                        sender.send(PlaybackUpdate::Loading).unwrap();
-
-                        if buffering_state.is_live == Some(false) {
-                            let tee = pipeline.by_name("tee").unwrap();
-                            let sinkpad = tee.static_pad("sink").unwrap();
-                            let probe_id = sinkpad
-                                .add_probe(
-                                    gstreamer::PadProbeType::BLOCK
-                                        | gstreamer::PadProbeType::BUFFER
-                                        | gstreamer::PadProbeType::BUFFER_LIST,
-                                    |_pad, _info| gstreamer::PadProbeReturn::Ok,
-                                )
-                                .unwrap();
-
-                            buffering_state.buffering_probe = Some((sinkpad, probe_id));
+
+                        if buffering_state.is_live == Some(false)
+                            && let Some(sinkpad) = pipeline
+                                .by_name("tee")
+                                .and_then(|tee| tee.static_pad("sink"))
+                        {
+                            let probe_id = sinkpad.add_probe(
+                                gstreamer::PadProbeType::BLOCK
+                                    | gstreamer::PadProbeType::BUFFER
+                                    | gstreamer::PadProbeType::BUFFER_LIST,
+                                |_pad, _info| gstreamer::PadProbeReturn::Ok,
+                            );
+
+                            buffering_state.buffering_probe =
+                                probe_id.map(|probe_id| (sinkpad, probe_id));
                             let _ = pipeline.set_state(gstreamer::State::Paused);
                         }
                     }
@@ -228,7 +800,7 @@ impl PlaybackManager {
 
                     if buffering_state.is_live == Some(false) && is_playing.load(Ordering::SeqCst) {
                         let _ = pipeline.set_state(gstreamer::State::Playing);
-                        sender.send(PlaybackUpdate::Playing).unwrap();
+                        events.publish(PlaybackUpdate::Playing);
                         if let Some((pad, probe_id)) = buffering_state.buffering_probe.take() {
                             pad.remove_probe(probe_id);
                         }
@@ -236,52 +808,125 @@ impl PlaybackManager {
                 }
             }
             MessageView::Element(element) => {
-                // Catch the end-of-stream messages from the filesink
-                let structure = element.structure().unwrap();
-                if structure.name() == "GstBinForwarded" {
-                    let message: gstreamer::message::Message = structure.get("message").unwrap();
-                    if let MessageView::Eos(_) = &message.view() {
-                        // Get recorderbin from message
-                        let recorderbin = match message
-                            .src()
-                            .and_then(|src| src.clone().downcast::<gstreamer::Bin>().ok())
-                        {
-                            Some(src) => src,
-                            None => return,
+                let Some(structure) = element.structure() else {
+                    return;
+                };
+
+                // Analysis results of the playback branch
+                match structure.name().as_str() {
+                    "level" => {
+                        let channels = |field| {
+                            structure
+                                .get::<glib::ValueArray>(field)
+                                .map(|values| {
+                                    values.iter().filter_map(|v| v.get::<f64>().ok()).collect()
+                                })
+                                .unwrap_or_default()
                         };
 
-                        // And then asynchronously remove it and set its state to Null
-                        pipeline.call_async(move |pipeline| {
-                            Self::destroy_recorderbin(pipeline.clone(), recorderbin);
-                        });
+                        events.publish(PlaybackUpdate::Level(AudioLevel {
+                            rms: channels("rms"),
+                            peak: channels("peak"),
+                        }));
                     }
+                    "spectrum" => {
+                        if let Ok(magnitudes) = structure.get::<gstreamer::List>("magnitude") {
+                            let magnitudes = magnitudes.iter().filter_map(|v| v.get::<f32>().ok());
+                            events.publish(PlaybackUpdate::Spectrum(magnitudes.collect()));
+                        }
+                    }
+                    _ => {}
                 }
             }
             // Error
             MessageView::Error(err) => {
-                let msg = err.error().to_string();
-                let _ = sender.send(PlaybackUpdate::Error(msg));
+                let mut msg = err.error().to_string();
+                if let Some(src) = err.src() {
+                    msg = format!("{}: {msg}", src.name());
+                }
+                if let Some(debug) = err.debug() {
+                    msg = format!("{msg} ({debug})");
+                }
+                events.publish(PlaybackUpdate::Error(msg));
             }
             _ => (),
         }
     }
 
+    /// Handle a raw title, from the stream or a now-playing provider
+    fn new_title(
+        pipeline: &gstreamer::Pipeline,
+        title_state: &mut TitleState,
+        events: &EventBus,
+        new_title: &str,
+        artist: Option<&str>,
+    ) {
+        // only send message if title really changed.
+        if title_state.current == new_title {
+            return;
+        }
+
+        title_state.current = new_title.to_string();
+        title_state.artist = artist.map(str::to_string);
+        let track_tags = Arc::new(title_state.parser.parse(new_title, artist));
+
+        // Mark where the new song starts in the session recording
+        if let Some(session) = &mut title_state.session
+            && let Some(offset) = Self::calculate_pipeline_offset(pipeline)
+        {
+            let offset = Duration::from_nanos(offset.saturating_sub(session.start));
+            session.cue_sheet.push(offset, track_tags.clone());
+        }
+
+        events.publish(PlaybackUpdate::NewSong(track_tags));
+    }
+
+    /// Apply some changes to the stream info and notify the receiver if anything actually changed
+    fn update_stream_info(
+        stream_info: &Mutex<StreamInfo>,
+        events: &EventBus,
+        update: impl FnOnce(&mut StreamInfo),
+    ) {
+        let mut stream_info = stream_info.lock().unwrap();
+        let mut new_info = stream_info.clone();
+        update(&mut new_info);
+
+        if *stream_info != new_info {
+            stream_info.clone_from(&new_info);
+            events.publish(PlaybackUpdate::StreamInfo(new_info));
+        }
+    }
+
     /// Stop the recording.
     /// set `discard_buffered_data` to true if you DO NOT WANT to
     /// properly close the file (The file might be incomplete in return!)
     /// You should do that if you want to switch streams, for example.
     pub fn stop_recording(&mut self, discard_buffered_data: bool) {
+        self.detach_recorder(discard_buffered_data, None);
+    }
+
+    /// Stop the recording, `finalized` is notified once it's completely written.
+    /// It isn't if the data is discarded
+    fn detach_recorder(&mut self, discard_buffered_data: bool, finalized: Option<Sender<()>>) {
         if !self.is_recording() {
             return;
         }
 
-        let recorderbin = self.recorderbin.clone().unwrap();
-        recorderbin.set_state(gstreamer::State::Null).unwrap();
+        let Some(recorderbin) = self.recorderbin.take() else {
+            return;
+        };
+
+        let session = self.title_state.lock().unwrap().session.take();
+        if discard_buffered_data {
+            let _ = recorderbin.set_state(gstreamer::State::Null);
+        } else if let Some(session) = session {
+            self.finish_session(&recorderbin, &session);
+        }
 
         // Get the source pad of the tee that is connected to the recorderbin
-        let recorderbin_sinkpad = recorderbin
-            .static_pad("sink")
-            .expect("Failed to get sink pad from recorderbin");
+        let Some(recorderbin_sinkpad) = recorderbin.static_pad("sink") else {
+            return;
+        };
 
         let tee_srcpad = match recorderbin_sinkpad.peer() {
             Some(peer) => peer,
@@ -291,28 +936,29 @@ impl PlaybackManager {
         // Once the tee source pad is idle and we wouldn't interfere with any data flow,
         // unlink the tee and the recording bin and finalize the recording bin
         // by sending it an end-of-stream event
-        //
-        // Once the end-of-stream event is handled by the whole recording bin, we get an
-        // end-of-stream message from it in the message handler and the shut down the
-        // recording bin and remove it from the pipeline
         tee_srcpad.add_probe(
             PadProbeType::IDLE,
             clone!(
                 #[weak(rename_to = pipeline)]
                 self.pipeline,
-                #[upgrade_or_panic]
+                #[upgrade_or]
+                gstreamer::PadProbeReturn::Remove,
                 move |tee_srcpad, _| {
-                    // Get the parent of the tee source pad, i.e. the tee itself
-                    let tee = tee_srcpad
+                    // Unlink the tee source pad and then release it from its parent, i.e. the tee itself
+                    let _ = tee_srcpad.unlink(&recorderbin_sinkpad);
+                    if let Some(tee) = tee_srcpad
                         .parent()
                         .and_then(|parent| parent.downcast::<gstreamer::Element>().ok())
-                        .expect("Failed to get tee source pad parent");
-
-                    // Unlink the tee source pad and then release it
-                    let _ = tee_srcpad.unlink(&recorderbin_sinkpad);
-                    tee.release_request_pad(tee_srcpad);
+                    {
+                        tee.release_request_pad(tee_srcpad);
+                    }
 
                     if !discard_buffered_data {
+                        // Take the recording bin out of the pipeline, so it can finish writing
+                        // even if the pipeline is stopped right after this
+                        let _ = pipeline.remove(&recorderbin);
+                        Self::finalize_recorderbin(&recorderbin, finalized.clone());
+
                         // Asynchronously send the end-of-stream event to the sinkpad as this might block for a
                         // while and our closure here might've been called from the main UI thread
                         let recorderbin_sinkpad = recorderbin_sinkpad.clone();
@@ -331,64 +977,201 @@ impl PlaybackManager {
         );
     }
 
+    /// Embed the chapters of a session recording and write its cue sheet next to it.
+    /// Has to happen before the end-of-stream, so the muxer can still write the chapters
+    fn finish_session(&self, recorderbin: &gstreamer::Bin, session: &SessionRecording) {
+        let end = Self::calculate_pipeline_offset(&self.pipeline)
+            .map(|offset| Duration::from_nanos(offset.saturating_sub(session.start)))
+            .unwrap_or_default();
+
+        if let Some(muxer) = recorderbin
+            .by_name("muxer")
+            .and_then(|muxer| muxer.dynamic_cast::<gstreamer::TocSetter>().ok())
+        {
+            muxer.set_toc(Some(&session.cue_sheet.to_toc(end)));
+        }
+
+        let cue_path = session.path.with_extension("cue");
+        if let Err(err) = fs::write(&cue_path, session.cue_sheet.to_cue()) {
+            self.events.publish(PlaybackUpdate::Error(format!(
+                "Unable to write {}: {err}",
+                cue_path.display()
+            )));
+        }
+    }
+
+    /// Shut the recording bin down once the end-of-stream event reached the filesink,
+    /// i.e. everything was written
+    fn finalize_recorderbin(recorderbin: &gstreamer::Bin, finalized: Option<Sender<()>>) {
+        let Some(filesink_pad) = recorderbin
+            .by_name("filesink")
+            .and_then(|filesink| filesink.static_pad("sink"))
+        else {
+            let _ = recorderbin.set_state(gstreamer::State::Null);
+            return;
+        };
+
+        // The probe keeps the bin alive until then, it isn't part of the pipeline anymore
+        let recorderbin = recorderbin.clone();
+        filesink_pad.add_probe(PadProbeType::EVENT_DOWNSTREAM, move |_, info| {
+            let Some(gstreamer::PadProbeData::Event(event)) = &info.data else {
+                return gstreamer::PadProbeReturn::Ok;
+            };
+            if event.type_() != gstreamer::EventType::Eos {
+                return gstreamer::PadProbeReturn::Ok;
+            }
+
+            let finalized = finalized.clone();
+            recorderbin.call_async(move |recorderbin| {
+                let _ = recorderbin.set_state(gstreamer::State::Null);
+                if let Some(finalized) = finalized {
+                    let _ = finalized.send(());
+                }
+            });
+            gstreamer::PadProbeReturn::Remove
+        });
+    }
+
     fn destroy_recorderbin(pipeline: gstreamer::Pipeline, recorderbin: gstreamer::Bin) {
+        let _ = recorderbin.set_state(gstreamer::State::Null);
         // Ignore if the bin was not in the pipeline anymore for whatever
         // reason. It's not a problem
         let _ = pipeline.remove(&recorderbin);
     }
 
-    fn calculate_pipeline_offset(pipeline: &gstreamer::Pipeline) -> u64 {
-        let clock_time = pipeline
-            .clock()
-            .expect("Could not get pipeline clock")
-            .time();
-        let base_time = pipeline
-            .base_time()
-            .expect("Could not get pipeline base time");
+    /// Returns `None` if the pipeline has no clock yet, i.e. it's not running
+    fn calculate_pipeline_offset(pipeline: &gstreamer::Pipeline) -> Option<u64> {
+        let clock_time = pipeline.clock()?.time();
+        let base_time = pipeline.base_time()?;
 
-        *clock_time - *base_time
+        Some(clock_time.saturating_sub(base_time).nseconds())
     }
 
-    fn set_state(&mut self, state: gstreamer::State) {
-        if state == gstreamer::State::Playing {
-            let mut buffering_state = self.buffering_state.lock().unwrap();
-            buffering_state.reset();
+    fn set_state(&mut self, state: gstreamer::State) -> Result<(), PlaybackError> {
+        if state == gstreamer::State::Null {
+            self.events.publish(PlaybackUpdate::Stopped);
+            self.title_state.lock().unwrap().current = String::new();
+            *self.stream_info.lock().unwrap() = StreamInfo::default();
         }
 
-        if state == gstreamer::State::Null {
-            self.sender.send(PlaybackUpdate::Stopped).unwrap();
-            *self.current_title.lock().unwrap() = String::new();
+        Self::change_state(&self.pipeline, &self.buffering_state, state)
+    }
+
+    /// NOTE: Since you can't use `self` from a thread, you have to provide the arguments manually
+    fn change_state(
+        pipeline: &gstreamer::Pipeline,
+        buffering_state: &Mutex<BufferingState>,
+        state: gstreamer::State,
+    ) -> Result<(), PlaybackError> {
+        if state == gstreamer::State::Playing {
+            buffering_state.lock().unwrap().reset();
         }
 
-        let res = self.pipeline.set_state(state);
+        let res = pipeline.set_state(state);
 
-        if state > gstreamer::State::Null && res.is_err() {
-            self.sender
-                .send(PlaybackUpdate::Error("Error!".to_string()))
-                .unwrap(); // FIXME
-            let _ = self.pipeline.set_state(gstreamer::State::Null);
-            return;
+        // The actual reason for the failure is posted on the bus
+        // and reaches the receiver as a `PlaybackUpdate::Error`
+        if state > gstreamer::State::Null
+            && let Err(err) = res
+        {
+            let _ = pipeline.set_state(gstreamer::State::Null);
+            return Err(PlaybackError::StateChange(err));
         }
 
         if state >= gstreamer::State::Paused {
-            let mut buffering_state = self.buffering_state.lock().unwrap();
+            let mut buffering_state = buffering_state.lock().unwrap();
             if buffering_state.is_live.is_none() {
                 let is_live = res == Ok(gstreamer::StateChangeSuccess::NoPreroll);
                 buffering_state.is_live = Some(is_live);
             }
         }
+
+        Ok(())
     }
 
-    /// Start playing the current stream
-    pub fn play(&mut self) {
-        self.set_state(gstreamer::State::Playing);
-        self.is_playing.store(true, Ordering::SeqCst);
+    /// Start playing the current stream, or as soon as the playlists of an HLS stream are read
+    pub fn play(&mut self) -> Result<(), PlaybackError> {
+        {
+            let source = self.source.lock().unwrap();
+            self.is_playing.store(true, Ordering::SeqCst);
+            if source.resolving {
+                return Ok(());
+            }
+        }
+
+        if let Err(err) = self.set_state(gstreamer::State::Playing) {
+            self.is_playing.store(false, Ordering::SeqCst);
+            return Err(err);
+        }
+        Ok(())
     }
 
     /// Stop playing the current stream
     pub fn stop(&mut self) {
-        self.set_state(gstreamer::State::Null);
-        self.is_playing.store(false, Ordering::SeqCst);
+        self.now_playing = None;
+        {
+            // Under the lock, so an HLS stream whose playlists are being read doesn't start after all
+            let _source = self.source.lock().unwrap();
+            self.is_playing.store(false, Ordering::SeqCst);
+        }
+        // Going to Null can't really fail
+        let _ = self.set_state(gstreamer::State::Null);
+    }
+
+    /// Poll `provider` every `interval` for what is playing, for stations that don't send titles
+    /// in their stream. Stops once the stream sends a title itself, or another station is played
+    pub fn start_now_playing(&mut self, provider: Box<dyn NowPlayingProvider>, interval: Duration) {
+        let client = match self.network.client() {
+            Ok(client) => client,
+            Err(err) => {
+                self.events.publish(PlaybackUpdate::Error(err.to_string()));
+                return;
+            }
+        };
+
+        let (stop_sender, stop_receiver) = mpsc::channel::<()>();
+        self.now_playing = Some(stop_sender);
+
+        let pipeline = self.pipeline.clone();
+        let title_state = self.title_state.clone();
+        let events = self.events.clone();
+        let is_playing = self.is_playing.clone();
+
+        thread::spawn(move || {
+            loop {
+                if title_state.lock().unwrap().inline {
+                    break;
+                }
+
+                // Failures are expected, most stations don't offer any of the sources
+                if is_playing.load(Ordering::SeqCst)
+                    && let Ok(Some(raw)) = provider.fetch(&client)
+                {
+                    let mut title_state = title_state.lock().unwrap();
+                    // The stream might have started sending titles while we were waiting,
+                    // or another station is playing by now
+                    if !title_state.inline && stop_receiver.try_recv() == Err(TryRecvError::Empty) {
+                        Self::new_title(
+                            &pipeline,
+                            &mut title_state,
+                            &events,
+                            &raw.title,
+                            raw.artist.as_deref(),
+                        );
+                    }
+                }
+
+                if stop_receiver.recv_timeout(interval) != Err(RecvTimeoutError::Timeout) {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Set how raw stream titles are split into artist and title,
+    /// e.g. when switching to a station with its own format
+    pub fn set_title_parser(&self, parser: TitleParser) {
+        self.title_state.lock().unwrap().parser = parser;
     }
 
     /// Check if a recorder is currently attached
@@ -396,59 +1179,254 @@ impl PlaybackManager {
         self.recorderbin.is_some()
     }
 
+    /// Check if the attached recorder is a session recording
+    pub fn is_session_recording(&self) -> bool {
+        self.title_state.lock().unwrap().session.is_some()
+    }
+
     /// Start recording the stream to some path
-    pub fn start_recording(&mut self, path: &Path) {
+    pub fn start_recording(&mut self, path: &Path) -> Result<(), PlaybackError> {
+        // The silence gate looks at the samples, so they are converted to floats first
+        let trim = if self.silence_trim.is_some() {
+            "audioconvert ! audio/x-raw,format=F32LE ! identity name=trim ! "
+        } else {
+            ""
+        };
+
+        self.attach_recorder(
+            path,
+            &format!(
+                "queue name=queue ! {trim}vorbisenc ! oggmux  ! filesink name=filesink async=false"
+            ),
+            self.silence_trim,
+        )?;
+        Ok(())
+    }
+
+    /// Start recording the stream into a single Matroska file (`.mka`) that keeps running
+    /// across title changes. Every new song is marked as a chapter and in a cue sheet,
+    /// which is written next to the recording once it is stopped
+    pub fn start_session_recording(
+        &mut self,
+        path: &Path,
+        title: &str,
+    ) -> Result<(), PlaybackError> {
+        let offset = self.attach_recorder(
+            path,
+            "queue name=queue ! vorbisenc ! matroskamux name=muxer ! filesink name=filesink async=false",
+            None,
+        )?;
+
+        let file = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        let mut cue_sheet = CueSheet::new(title, &file);
+
+        let mut title_state = self.title_state.lock().unwrap();
+        if !title_state.current.is_empty() {
+            let track_tags = title_state
+                .parser
+                .parse(&title_state.current, title_state.artist.as_deref());
+            cue_sheet.push(Duration::ZERO, Arc::new(track_tags));
+        }
+
+        title_state.session = Some(SessionRecording {
+            cue_sheet,
+            start: offset,
+            path: path.to_path_buf(),
+        });
+        Ok(())
+    }
+
+    /// Attach a recorderbin built from `description` to the tee, writing to `path`.
+    /// Returns the running time of the pipeline at which the recording starts.
+    /// With `silence_trim` the bin needs an element named `trim` that outputs F32LE samples
+    fn attach_recorder(
+        &mut self,
+        path: &Path,
+        description: &str,
+        silence_trim: Option<SilenceTrim>,
+    ) -> Result<u64, PlaybackError> {
         if self.is_recording() {
             self.stop_recording(false);
         }
 
+        let location = path
+            .to_str()
+            .ok_or_else(|| PlaybackError::InvalidPath(path.to_path_buf()))?;
+
+        // We need to set an offset, otherwise the length of the recorded title would be
+        // wrong. Get current clock time and calculate offset
+        let offset =
+            Self::calculate_pipeline_offset(&self.pipeline).ok_or(PlaybackError::NotPlaying)?;
+
         // Create actual recorderbin
-        let description =
-            "queue name=queue ! vorbisenc ! oggmux  ! filesink name=filesink async=false";
         let recorderbin = gstreamer::parse::bin_from_description(description, true)
-            .expect("Unable to create recorderbin");
+            .map_err(PlaybackError::PipelineCreation)?;
         recorderbin.set_message_forward(true);
 
-        // We need to set an offset, otherwise the length of the recorded title would be
-        // wrong. Get current clock time and calculate offset
-        let offset = Self::calculate_pipeline_offset(&self.pipeline);
         let queue_srcpad = recorderbin
             .by_name("queue")
-            .unwrap()
-            .static_pad("src")
-            .unwrap();
+            .and_then(|queue| queue.static_pad("src"))
+            .ok_or(PlaybackError::MissingElement("queue"))?;
         queue_srcpad.set_offset(offset.into_negative().try_into().unwrap_or_default());
 
         // Set recording path
-        let filesink = recorderbin.by_name("filesink").unwrap();
-        filesink.set_property("location", path.to_str().unwrap());
+        let filesink = recorderbin
+            .by_name("filesink")
+            .ok_or(PlaybackError::MissingElement("filesink"))?;
+        filesink.set_property("location", location);
+
+        if let Some(silence_trim) = silence_trim {
+            Self::trim_silence(&recorderbin, silence_trim)?;
+        }
 
         // First try setting the recording bin to playing: if this fails we know this
         // before it potentially interfered with the other part of the pipeline
-        recorderbin
-            .set_state(gstreamer::State::Playing)
-            .unwrap_or_else(|_| panic!("Failed to record song {}", path.to_str().unwrap_or("---")));
+        if let Err(err) = recorderbin.set_state(gstreamer::State::Playing) {
+            let _ = recorderbin.set_state(gstreamer::State::Null);
+            return Err(PlaybackError::StateChange(err));
+        }
 
         // Add new recorderbin to the pipeline
         self.pipeline
             .add(&recorderbin)
-            .expect("Unable to add recorderbin to pipeline");
+            .map_err(PlaybackError::Bin)?;
 
         // Get our tee element by name, request a new source pad from it and then link
         // that to our recording bin to actually start receiving data
-        let tee = self.pipeline.by_name("tee").unwrap();
-        let tee_srcpad = tee
-            .request_pad_simple("src_%u")
-            .expect("Failed to request new pad from tee");
-        let sinkpad = recorderbin
-            .static_pad("sink")
-            .expect("Failed to get sink pad from recorderbin");
+        let pads = self.element("tee").and_then(|tee| {
+            let tee_srcpad = tee
+                .request_pad_simple("src_%u")
+                .ok_or(PlaybackError::MissingElement("tee src pad"))?;
+            let sinkpad = recorderbin
+                .static_pad("sink")
+                .ok_or(PlaybackError::MissingElement("recorderbin sink pad"))?;
+            Ok((tee, tee_srcpad, sinkpad))
+        });
 
         // Link tee srcpad with the sinkpad of the recorderbin
-        tee_srcpad
-            .link(&sinkpad)
-            .expect("Unable to link tee srcpad with recorderbin sinkpad");
+        let linked = pads.and_then(|(tee, tee_srcpad, sinkpad)| {
+            tee_srcpad.link(&sinkpad).map_err(|err| {
+                tee.release_request_pad(&tee_srcpad);
+                PlaybackError::Link(err)
+            })
+        });
+
+        if let Err(err) = linked {
+            let _ = recorderbin.set_state(gstreamer::State::Null);
+            Self::destroy_recorderbin(self.pipeline.clone(), recorderbin);
+            return Err(err);
+        }
 
         self.recorderbin = Some(recorderbin);
+        Ok(offset)
+    }
+
+    /// Pass the audio leaving the `trim` element of a recorderbin through a `SilenceGate`
+    fn trim_silence(recorderbin: &gstreamer::Bin, trim: SilenceTrim) -> Result<(), PlaybackError> {
+        let pad = recorderbin
+            .by_name("trim")
+            .and_then(|trim| trim.static_pad("src"))
+            .ok_or(PlaybackError::MissingElement("trim"))?;
+
+        let gate = Mutex::new(SilenceGate::new(trim));
+        pad.add_probe(
+            PadProbeType::BUFFER | PadProbeType::EVENT_DOWNSTREAM,
+            move |pad, info| {
+                let mut gate = gate.lock().unwrap();
+                let (mut buffers, is_buffer) = match &info.data {
+                    Some(gstreamer::PadProbeData::Buffer(buffer)) => {
+                        (gate.push(buffer.clone()), true)
+                    }
+                    Some(gstreamer::PadProbeData::Event(event))
+                        if event.type_() == gstreamer::EventType::Eos =>
+                    {
+                        (gate.finish(), false)
+                    }
+                    _ => return gstreamer::PadProbeReturn::Ok,
+                };
+
+                // The last buffer takes the place of the current one, held back ones go before it
+                let current = if is_buffer { buffers.pop() } else { None };
+                if let Some(peer) = pad.peer() {
+                    for buffer in buffers {
+                        let _ = peer.chain(buffer);
+                    }
+                }
+
+                match current {
+                    Some(buffer) => {
+                        info.data = Some(gstreamer::PadProbeData::Buffer(buffer));
+                        gstreamer::PadProbeReturn::Ok
+                    }
+                    None if is_buffer => gstreamer::PadProbeReturn::Drop,
+                    None => gstreamer::PadProbeReturn::Ok,
+                }
+            },
+        );
+
+        Ok(())
+    }
+}
+
+impl Drop for PlaybackManager {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+    use crate::output::tests::{init_playback, is_level, tone_file, wait_for};
+
+    /// Wether the last page of an Ogg file is marked as the end of the stream
+    fn is_finalized(data: &[u8]) -> bool {
+        let last_page = data.windows(4).rposition(|window| window == b"OggS");
+        last_page.is_some_and(|page| data.get(page + 5).is_some_and(|flags| flags & 0x04 != 0))
+    }
+
+    #[test]
+    fn managers_shut_down_independently() {
+        if !init_playback() {
+            return;
+        }
+
+        let dir = env::temp_dir();
+        let source = dir.join(format!("antenna-playback-{}.wav", process::id()));
+        let recording = dir.join(format!("antenna-playback-{}.ogg", process::id()));
+        let uri = tone_file(&source, Duration::from_secs(20));
+        let options = PlaybackOptions {
+            sink: AudioSink::Fake,
+            ..Default::default()
+        };
+
+        let mut first = PlaybackManager::new(&options).unwrap();
+        let mut second = PlaybackManager::new(&options).unwrap();
+        let first_updates = first.subscribe();
+        let second_updates = second.subscribe();
+        for manager in [&mut first, &mut second] {
+            manager.set_source_uri(&uri).unwrap();
+            manager.play().unwrap();
+        }
+        assert!(wait_for(&first_updates, is_level));
+        assert!(wait_for(&second_updates, is_level));
+
+        first.start_recording(&recording).unwrap();
+        thread::sleep(Duration::from_secs(1));
+        drop(first);
+
+        // Shutting one down finished its recording, and left the other one alone
+        assert!(is_finalized(&fs::read(&recording).unwrap()));
+        second_updates.try_iter().for_each(drop);
+        assert!(wait_for(&second_updates, is_level));
+
+        drop(second);
+        fs::remove_file(source).unwrap();
+        fs::remove_file(recording).unwrap();
     }
 }
@@ -0,0 +1,426 @@
+use std::{
+    fmt::Display,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, RecvTimeoutError, Sender},
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::{StatusCode, blocking::Client};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    network::{NetworkError, NetworkSettings},
+    playback::TrackTags,
+};
+
+/// The public ListenBrainz server
+pub const DEFAULT_URL: &str = "https://api.listenbrainz.org";
+
+/// Most listens ListenBrainz takes in one submission
+const MAX_BATCH: usize = 100;
+
+/// Longest wait between retries of a failed submission
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// Error type that is returned when listens can't be queued or submitted
+#[derive(Debug)]
+pub enum ScrobbleError {
+    NetworkError(reqwest::Error),
+    /// The server refused the submission, with its status and message
+    Rejected(StatusCode, String),
+    QueueError(io::Error),
+}
+
+impl Display for ScrobbleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NetworkError(err) => write!(f, "{err}"),
+            Self::Rejected(status, msg) => write!(f, "Listens rejected ({status}): {msg}"),
+            Self::QueueError(err) => write!(f, "Unable to access scrobble queue: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ScrobbleError {}
+
+/// A track that was listened to long enough
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Listen {
+    /// When the track started, in seconds since the unix epoch
+    pub listened_at: u64,
+    pub artist: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScrobbleSettings {
+    /// Root of a server that implements the ListenBrainz API
+    pub url: String,
+    /// User token of the account to submit to
+    pub token: String,
+    /// Tracks that were listened to for less than this aren't submitted
+    pub min_listen: Duration,
+    /// Listens that weren't submitted yet are kept here, as JSON lines
+    pub queue_path: PathBuf,
+    /// First wait after a failed submission, it doubles with every failure
+    pub retry_delay: Duration,
+}
+
+/// Listens that still have to be submitted, kept on disk so they survive restarts and outages
+#[derive(Debug)]
+struct ListenQueue {
+    path: PathBuf,
+}
+
+impl ListenQueue {
+    /// Lines that can't be read are skipped
+    fn read(&self) -> io::Result<Vec<Listen>> {
+        match fs::read_to_string(&self.path) {
+            Ok(data) => Ok(data
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn push(&self, listen: &Listen) -> io::Result<()> {
+        let mut line = serde_json::to_string(listen).map_err(io::Error::other)?;
+        line.push('\n');
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())
+    }
+
+    /// Remove the `count` oldest listens, once they were submitted
+    fn remove_oldest(&self, count: usize) -> io::Result<()> {
+        let mut data = String::new();
+        for listen in self.read()?.iter().skip(count) {
+            data.push_str(&serde_json::to_string(listen).map_err(io::Error::other)?);
+            data.push('\n');
+        }
+
+        let temp = self.path.with_extension("tmp");
+        fs::write(&temp, data)?;
+        fs::rename(temp, &self.path)
+    }
+}
+
+/// The track that is being listened to
+#[derive(Debug)]
+struct Current {
+    tags: TrackTags,
+    listened_at: SystemTime,
+    start: Instant,
+}
+
+/// Submits the tracks that were listened to long enough to ListenBrainz.
+/// Listens are queued on disk first and submitted by a background thread,
+/// which retries with a growing delay while the server can't be reached
+#[derive(Debug)]
+pub struct Scrobbler {
+    min_listen: Duration,
+    queue: Arc<Mutex<ListenQueue>>,
+    /// Wakes the submitting thread up, it quits once this is dropped
+    wake: Sender<()>,
+    last_error: Arc<Mutex<Option<String>>>,
+    current: Option<Current>,
+}
+
+impl Scrobbler {
+    /// Starts submitting, including the listens that are still queued from before
+    pub fn new(
+        settings: ScrobbleSettings,
+        network: &NetworkSettings,
+    ) -> Result<Self, NetworkError> {
+        let client = network.client()?;
+        let queue = Arc::new(Mutex::new(ListenQueue {
+            path: settings.queue_path.clone(),
+        }));
+        let last_error = Arc::new(Mutex::new(None));
+        let (wake, wake_receiver) = mpsc::channel();
+        let min_listen = settings.min_listen;
+
+        let thread_queue = Arc::clone(&queue);
+        let thread_error = Arc::clone(&last_error);
+        thread::spawn(move || {
+            let mut delay = settings.retry_delay;
+
+            loop {
+                let result = thread_queue
+                    .lock()
+                    .unwrap()
+                    .read()
+                    .map_err(ScrobbleError::QueueError);
+                let batch: Vec<Listen> = match result {
+                    Ok(listens) => listens.into_iter().take(MAX_BATCH).collect(),
+                    Err(err) => {
+                        *thread_error.lock().unwrap() = Some(err.to_string());
+                        vec![]
+                    }
+                };
+
+                let wait = if batch.is_empty() {
+                    None
+                } else {
+                    let result = submit(&client, &settings, &batch);
+                    match result {
+                        // Listens the server can't take never will be, drop them as well
+                        Ok(()) | Err(ScrobbleError::Rejected(StatusCode::BAD_REQUEST, _)) => {
+                            let removed = thread_queue.lock().unwrap().remove_oldest(batch.len());
+                            *thread_error.lock().unwrap() = match (result, removed) {
+                                (Err(err), _) => Some(err.to_string()),
+                                (_, Err(err)) => Some(ScrobbleError::QueueError(err).to_string()),
+                                _ => None,
+                            };
+                            delay = settings.retry_delay;
+                            continue;
+                        }
+                        Err(err) => {
+                            *thread_error.lock().unwrap() = Some(err.to_string());
+                            let wait = delay;
+                            delay = (delay * 2).min(MAX_RETRY_DELAY);
+                            Some(wait)
+                        }
+                    }
+                };
+
+                // Wait for new listens, or until it is time to retry
+                let woken = match wait {
+                    Some(wait) => wake_receiver.recv_timeout(wait),
+                    None => wake_receiver
+                        .recv()
+                        .map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                if woken == Err(RecvTimeoutError::Disconnected) {
+                    return;
+                }
+            }
+        });
+
+        Ok(Self {
+            min_listen,
+            queue,
+            wake,
+            last_error,
+            current: None,
+        })
+    }
+
+    /// Finish the track that was playing, queueing it if it was listened to long enough,
+    /// and start timing `tags`. `None` if nothing is playing, or it shouldn't be scrobbled.
+    /// Tracks without an artist can't be submitted and are left out
+    pub fn new_track(&mut self, tags: Option<&TrackTags>) -> Result<(), ScrobbleError> {
+        let finished = self.current.take();
+
+        self.current = tags
+            .filter(|tags| tags.artist.is_some())
+            .map(|tags| Current {
+                tags: tags.clone(),
+                listened_at: SystemTime::now(),
+                start: Instant::now(),
+            });
+
+        let Some(finished) = finished else {
+            return Ok(());
+        };
+        if finished.start.elapsed() < self.min_listen {
+            return Ok(());
+        }
+
+        let listen = Listen {
+            listened_at: finished
+                .listened_at
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs()),
+            artist: finished.tags.artist.unwrap_or_default(),
+            title: finished.tags.title,
+        };
+
+        self.queue
+            .lock()
+            .unwrap()
+            .push(&listen)
+            .map_err(ScrobbleError::QueueError)?;
+        let _ = self.wake.send(());
+
+        Ok(())
+    }
+
+    /// How many listens weren't submitted yet
+    pub fn pending(&self) -> usize {
+        self.queue
+            .lock()
+            .unwrap()
+            .read()
+            .map_or(0, |listens| listens.len())
+    }
+
+    /// Why the last submission failed, `None` once one succeeds
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().unwrap().clone()
+    }
+}
+
+/// Submit listens through the `submit-listens` API
+fn submit(
+    client: &Client,
+    settings: &ScrobbleSettings,
+    listens: &[Listen],
+) -> Result<(), ScrobbleError> {
+    let payload: Vec<_> = listens
+        .iter()
+        .map(|listen| {
+            json!({
+                "listened_at": listen.listened_at,
+                "track_metadata": {
+                    "artist_name": listen.artist,
+                    "track_name": listen.title,
+                    "additional_info": {
+                        "submission_client": "antenna",
+                        "submission_client_version": env!("CARGO_PKG_VERSION"),
+                    },
+                },
+            })
+        })
+        .collect();
+    let body = json!({
+        "listen_type": if listens.len() == 1 { "single" } else { "import" },
+        "payload": payload,
+    });
+
+    let response = client
+        .post(format!(
+            "{}/1/submit-listens",
+            settings.url.trim_end_matches('/')
+        ))
+        .header("Authorization", format!("Token {}", settings.token))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .map_err(ScrobbleError::NetworkError)?;
+
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(ScrobbleError::Rejected(
+            status,
+            response.text().unwrap_or_default(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env,
+        io::{BufRead, BufReader, Read},
+        net::TcpListener,
+        process,
+    };
+
+    use super::*;
+
+    /// Stands in for ListenBrainz, failing the first submission.
+    /// Hands the `Authorization` header and body of every request to the receiver
+    fn fake_listenbrainz() -> (String, mpsc::Receiver<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for (attempt, stream) in listener.incoming().enumerate() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(&stream);
+
+                let mut authorization = String::new();
+                let mut length = 0;
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                    let lower = line.to_lowercase();
+                    if let Some(value) = lower.strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    } else if lower.starts_with("authorization:") {
+                        authorization = line["authorization:".len()..].trim().to_string();
+                    }
+                    line.clear();
+                }
+
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let _ = sender.send((authorization, String::from_utf8(body).unwrap()));
+
+                let response = if attempt == 0 {
+                    "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                } else {
+                    "HTTP/1.1 200 OK\r\nContent-Length: 16\r\nConnection: close\r\n\r\n{\"status\":\"ok\"}"
+                };
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        (url, receiver)
+    }
+
+    #[test]
+    fn submits_with_retry() {
+        let (url, requests) = fake_listenbrainz();
+        let queue_path = env::temp_dir().join(format!("antenna-scrobble-{}.jsonl", process::id()));
+        let _ = fs::remove_file(&queue_path);
+
+        let mut scrobbler = Scrobbler::new(
+            ScrobbleSettings {
+                url,
+                token: "secret".to_string(),
+                min_listen: Duration::ZERO,
+                queue_path: queue_path.clone(),
+                retry_delay: Duration::from_millis(50),
+            },
+            &NetworkSettings::default(),
+        )
+        .unwrap();
+
+        let track = TrackTags {
+            title: "One More Time".to_string(),
+            artist: Some("Daft Punk".to_string()),
+        };
+        let jingle = TrackTags {
+            title: "Station ID".to_string(),
+            artist: None,
+        };
+        scrobbler.new_track(Some(&track)).unwrap();
+        scrobbler.new_track(Some(&jingle)).unwrap();
+        scrobbler.new_track(None).unwrap();
+
+        // The failed submission is retried with the same listen
+        let timeout = Duration::from_secs(5);
+        for _ in 0..2 {
+            let (authorization, body) = requests.recv_timeout(timeout).unwrap();
+            assert_eq!(authorization, "Token secret");
+            assert!(body.contains(r#""artist_name":"Daft Punk""#));
+            assert!(body.contains(r#""track_name":"One More Time""#));
+        }
+
+        let start = Instant::now();
+        while scrobbler.pending() > 0 && start.elapsed() < timeout {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(scrobbler.pending(), 0);
+        // The jingle has no artist
+        assert!(requests.recv_timeout(Duration::from_millis(200)).is_err());
+
+        let _ = fs::remove_file(queue_path);
+    }
+}
@@ -0,0 +1,140 @@
+use std::{fmt::Write, path::PathBuf, sync::Arc, time::Duration};
+
+use gstreamer::{TagList, TagMergeMode, Toc, TocEntry, TocEntryType, TocScope};
+
+use crate::playback::TrackTags;
+
+/// CD frames per second, the resolution of cue sheet timestamps
+const CUE_FRAMES: u128 = 75;
+
+/// A point in a session recording where a new track starts
+#[derive(Debug, Clone, PartialEq)]
+pub struct CuePoint {
+    /// Time since the start of the recording
+    pub offset: Duration,
+    pub tags: Arc<TrackTags>,
+}
+
+/// All the tracks of a session recording
+#[derive(Debug, Clone, PartialEq)]
+pub struct CueSheet {
+    /// Title of the whole recording, usually the station name
+    pub title: String,
+    /// Name of the audio file the cue sheet belongs to
+    pub file: String,
+    pub points: Vec<CuePoint>,
+}
+
+impl CueSheet {
+    pub fn new(title: &str, file: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            file: file.to_string(),
+            points: vec![],
+        }
+    }
+
+    pub fn push(&mut self, offset: Duration, tags: Arc<TrackTags>) {
+        self.points.push(CuePoint { offset, tags });
+    }
+
+    /// Formats the cue sheet as a `.cue` file
+    pub fn to_cue(&self) -> String {
+        let mut cue = String::new();
+        let _ = writeln!(cue, "TITLE {}", quote(&self.title));
+        let _ = writeln!(cue, "FILE {} WAVE", quote(&self.file));
+
+        for (i, point) in self.points.iter().enumerate() {
+            let frames = point.offset.as_millis() * CUE_FRAMES / 1000;
+            let (minutes, seconds, frames) = (
+                frames / (60 * CUE_FRAMES),
+                frames / CUE_FRAMES % 60,
+                frames % CUE_FRAMES,
+            );
+
+            let _ = writeln!(cue, "  TRACK {:02} AUDIO", i + 1);
+            let _ = writeln!(cue, "    TITLE {}", quote(&point.tags.title));
+            if let Some(artist) = &point.tags.artist {
+                let _ = writeln!(cue, "    PERFORMER {}", quote(artist));
+            }
+            let _ = writeln!(cue, "    INDEX 01 {minutes:02}:{seconds:02}:{frames:02}");
+        }
+
+        cue
+    }
+
+    /// Creates chapter markers for the tracks, the last one ending at `end`
+    pub(crate) fn to_toc(&self, end: Duration) -> Toc {
+        let mut edition = TocEntry::new(TocEntryType::Edition, "session");
+
+        for (i, point) in self.points.iter().enumerate() {
+            let stop = self.points.get(i + 1).map_or(end, |next| next.offset);
+            let mut chapter = TocEntry::new(TocEntryType::Chapter, &format!("track{}", i + 1));
+
+            let mut tags = TagList::new();
+            {
+                let tags = tags.get_mut().unwrap();
+                tags.add::<gstreamer::tags::Title>(
+                    &point.tags.title.as_str(),
+                    TagMergeMode::Replace,
+                );
+                if let Some(artist) = &point.tags.artist {
+                    tags.add::<gstreamer::tags::Artist>(&artist.as_str(), TagMergeMode::Replace);
+                }
+            }
+
+            let chapter_mut = chapter.get_mut().unwrap();
+            chapter_mut
+                .set_start_stop_times(point.offset.as_nanos() as i64, stop.as_nanos() as i64);
+            chapter_mut.set_tags(tags);
+
+            edition.get_mut().unwrap().append_sub_entry(chapter);
+        }
+
+        let mut toc = Toc::new(TocScope::Global);
+        toc.get_mut().unwrap().append_entry(edition);
+        toc
+    }
+}
+
+/// Quotes a string for a cue sheet, which has no escaping
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "'"))
+}
+
+/// A session recording that is currently running
+#[derive(Debug)]
+pub(crate) struct SessionRecording {
+    pub cue_sheet: CueSheet,
+    /// Running time of the pipeline when the recording started (in nanoseconds)
+    pub start: u64,
+    pub path: PathBuf,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cue_sheet() {
+        let mut sheet = CueSheet::new("Radio \"Bossa\"", "session.mka");
+        sheet.push(Duration::ZERO, Arc::new(TrackTags::mock("First", None)));
+        sheet.push(
+            Duration::from_millis(61 * 60 * 1000 + 2500),
+            Arc::new(TrackTags::mock("Second", Some("Someone"))),
+        );
+
+        assert_eq!(
+            sheet.to_cue(),
+            "TITLE \"Radio 'Bossa'\"\n\
+             FILE \"session.mka\" WAVE\n  \
+               TRACK 01 AUDIO\n    \
+                 TITLE \"First\"\n    \
+                 INDEX 01 00:00:00\n  \
+               TRACK 02 AUDIO\n    \
+                 TITLE \"Second\"\n    \
+                 PERFORMER \"Someone\"\n    \
+                 INDEX 01 61:02:37\n"
+        );
+    }
+}
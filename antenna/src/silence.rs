@@ -0,0 +1,181 @@
+use std::time::Duration;
+
+use gstreamer::ClockTime;
+
+use crate::playback::SilenceTrim;
+
+/// Trailing silence isn't held back for longer than this, so a long pause within a song
+/// doesn't pile up in memory. Songs ending in a longer silence keep some of it
+const MAX_HELD_SILENCE: Duration = Duration::from_secs(30);
+
+/// Cuts the silence at the start and the end of a song recording, pauses within the song are kept.
+/// Silent buffers are held back until it is clear wether sound follows them
+#[derive(Debug)]
+pub(crate) struct SilenceGate {
+    min_duration: Duration,
+    /// Peak amplitude below which a buffer counts as silent
+    max_amplitude: f32,
+    held: Vec<gstreamer::Buffer>,
+    held_duration: Duration,
+    /// Timestamp of the first buffer, where the recording starts
+    start: Option<ClockTime>,
+    /// Wether there was sound yet, any silence before it is leading silence
+    heard: bool,
+    /// How far timestamps are moved back, to close the gap of cut leading silence
+    shift: ClockTime,
+}
+
+impl SilenceGate {
+    pub(crate) fn new(trim: SilenceTrim) -> Self {
+        Self {
+            min_duration: trim.min_duration,
+            max_amplitude: 10f32.powf(trim.threshold as f32 / 20.0),
+            held: vec![],
+            held_duration: Duration::ZERO,
+            start: None,
+            heard: false,
+            shift: ClockTime::ZERO,
+        }
+    }
+
+    /// The buffers to pass on once `buffer` arrived, in order.
+    /// `buffer` has to hold interleaved 32 bit float samples
+    pub(crate) fn push(&mut self, buffer: gstreamer::Buffer) -> Vec<gstreamer::Buffer> {
+        let pts = buffer.pts().unwrap_or_default();
+        let start = *self.start.get_or_insert(pts);
+
+        if self.is_silent(&buffer) {
+            self.held_duration += buffer
+                .duration()
+                .map_or(Duration::ZERO, |duration| duration.into());
+            self.held.push(buffer);
+
+            if !self.heard && self.held_duration >= self.min_duration {
+                // Long enough to be cut, no need to keep it around
+                self.held.clear();
+            } else if self.heard && self.held_duration > MAX_HELD_SILENCE {
+                return self.release();
+            }
+            return vec![];
+        }
+
+        if !self.heard {
+            self.heard = true;
+            if self.held_duration >= self.min_duration {
+                self.held.clear();
+                self.shift = pts.saturating_sub(start);
+            }
+        }
+
+        let mut buffers = self.release();
+        buffers.push(self.shifted(buffer));
+        buffers
+    }
+
+    /// The buffers to pass on at the end of the recording, trailing silence is dropped if it's long enough
+    pub(crate) fn finish(&mut self) -> Vec<gstreamer::Buffer> {
+        if self.held_duration >= self.min_duration {
+            self.held.clear();
+            self.held_duration = Duration::ZERO;
+            vec![]
+        } else {
+            self.release()
+        }
+    }
+
+    fn release(&mut self) -> Vec<gstreamer::Buffer> {
+        self.held_duration = Duration::ZERO;
+        let held = std::mem::take(&mut self.held);
+        held.into_iter()
+            .map(|buffer| self.shifted(buffer))
+            .collect()
+    }
+
+    fn shifted(&self, mut buffer: gstreamer::Buffer) -> gstreamer::Buffer {
+        if self.shift > ClockTime::ZERO {
+            let buffer = buffer.make_mut();
+            buffer.set_pts(buffer.pts().map(|pts| pts.saturating_sub(self.shift)));
+        }
+        buffer
+    }
+
+    fn is_silent(&self, buffer: &gstreamer::Buffer) -> bool {
+        let Ok(map) = buffer.map_readable() else {
+            return false;
+        };
+
+        map.chunks_exact(4)
+            .map(|sample| f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]))
+            .all(|sample| sample.abs() < self.max_amplitude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUFFER_LENGTH: Duration = Duration::from_millis(500);
+
+    /// Half a second starting at `index` half seconds, of silence or a loud signal
+    fn buffer(index: u64, loud: bool) -> gstreamer::Buffer {
+        let sample: f32 = if loud { 0.5 } else { 0.0001 };
+        let data: Vec<u8> = (0..64).flat_map(|_| sample.to_le_bytes()).collect();
+
+        let mut buffer = gstreamer::Buffer::from_mut_slice(data);
+        let buffer_ref = buffer.get_mut().unwrap();
+        buffer_ref.set_pts(ClockTime::from_nseconds(
+            index * BUFFER_LENGTH.as_nanos() as u64,
+        ));
+        buffer_ref.set_duration(ClockTime::from_nseconds(BUFFER_LENGTH.as_nanos() as u64));
+        buffer
+    }
+
+    fn starts(buffers: &[gstreamer::Buffer]) -> Vec<u64> {
+        buffers
+            .iter()
+            .map(|buffer| buffer.pts().unwrap().mseconds())
+            .collect()
+    }
+
+    #[test]
+    fn trims_edges() {
+        gstreamer::init().unwrap();
+        let mut gate = SilenceGate::new(SilenceTrim {
+            threshold: -50,
+            min_duration: Duration::from_secs(1),
+        });
+
+        // Leading silence is cut, the song is moved to the start
+        let mut passed = vec![];
+        for (index, loud) in [false, false, false, true, false, true]
+            .into_iter()
+            .enumerate()
+        {
+            passed.extend(gate.push(buffer(index as u64, loud)));
+        }
+        // The pause within the song is kept, the trailing silence is cut
+        for index in 6..10 {
+            passed.extend(gate.push(buffer(index, false)));
+        }
+        passed.extend(gate.finish());
+
+        assert_eq!(starts(&passed), [0, 500, 1000]);
+    }
+
+    #[test]
+    fn keeps_short_edges() {
+        gstreamer::init().unwrap();
+        let mut gate = SilenceGate::new(SilenceTrim {
+            threshold: -50,
+            min_duration: Duration::from_secs(2),
+        });
+
+        let mut passed = vec![];
+        for (index, loud) in [false, true, false].into_iter().enumerate() {
+            passed.extend(gate.push(buffer(index as u64, loud)));
+        }
+        passed.extend(gate.finish());
+
+        assert_eq!(starts(&passed), [0, 500, 1000]);
+    }
+}
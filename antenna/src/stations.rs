@@ -13,11 +13,11 @@ pub struct Station {
 }
 
 impl Station {
-    #[cfg(test)]
-    fn mock(name: &str, country: &str) -> Self {
+    #[cfg(any(test, feature = "mock"))]
+    pub fn mock(name: &str, country: &str) -> Self {
         Self {
-            url: "".to_string(),
-            id: "".to_string(),
+            url: format!("http://radio.example/{name}"),
+            id: name.to_string(),
             country: country.to_string(),
             name: name.to_string(),
             votes: 0,
@@ -0,0 +1,194 @@
+use std::sync::LazyLock;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::charset::{self, Encoding};
+use crate::playback::TrackTags;
+
+/// Separators most stations put between the artist and the title
+pub const DEFAULT_SEPARATORS: [&str; 3] = [" - ", " – ", " — "];
+
+/// Release markers that don't belong to the title, like `(Radio Edit)` or `[Explicit]`
+static JUNK_MARKERS: LazyLock<Regex> = LazyLock::new(|| {
+    RegexBuilder::new(
+        r"\s*[(\[](radio edit|radio version|single version|album version|explicit|clean|official (music )?video|official audio|(\d{4} )?remaster(ed)?( \d{4})?)[)\]]",
+    )
+    .case_insensitive(true)
+    .build()
+    .unwrap()
+});
+
+/// Links stations append to their titles, e.g. `Title - www.station.com`
+static TRAILING_URL: LazyLock<Regex> = LazyLock::new(|| {
+    RegexBuilder::new(r"\s*[-|@~]?\s*(https?://|www\.)\S+\s*$")
+        .case_insensitive(true)
+        .build()
+        .unwrap()
+});
+
+/// Turns the raw titles of a stream into artist and title.
+/// ICY streams only send a combined `StreamTitle`, usually `Artist - Title`
+#[derive(Debug, Clone)]
+pub struct TitleParser {
+    /// Tried before the separators, the first one that matches wins.
+    /// Has to have `title` and may have `artist` named groups, e.g. `^(?<title>.+) by (?<artist>.+)$`
+    pub patterns: Vec<Regex>,
+    /// The title is split at the first of these in it
+    pub separators: Vec<String>,
+    /// Wether to strip release markers and trailing URLs
+    pub strip_junk: bool,
+    /// Charset the station sends its titles in, if they arrive garbled and detection fails
+    pub encoding: Option<&'static Encoding>,
+}
+
+impl Default for TitleParser {
+    fn default() -> Self {
+        Self {
+            patterns: vec![],
+            separators: DEFAULT_SEPARATORS.map(str::to_string).to_vec(),
+            strip_junk: false,
+            encoding: None,
+        }
+    }
+}
+
+impl TitleParser {
+    /// Repairs the charset of the tags and splits `title`,
+    /// unless the stream sent an artist of its own
+    pub fn parse(&self, title: &str, artist: Option<&str>) -> TrackTags {
+        let title = charset::repair(title, self.encoding);
+        let artist = artist.map(|artist| charset::repair(artist, self.encoding));
+
+        let (artist, title) = match &artist {
+            Some(artist) => (Some(artist.as_str()), title.as_str()),
+            None => self.split(&title),
+        };
+
+        TrackTags {
+            title: self.clean(title),
+            artist: artist
+                .map(|artist| self.clean(artist))
+                .filter(|a| !a.is_empty()),
+        }
+    }
+
+    fn split<'a>(&self, title: &'a str) -> (Option<&'a str>, &'a str) {
+        let captured = self.patterns.iter().find_map(|pattern| {
+            let captures = pattern.captures(title)?;
+            let title = captures.name("title")?.as_str();
+            Some((captures.name("artist").map(|artist| artist.as_str()), title))
+        });
+
+        // Split at whichever separator comes first
+        let separated = || {
+            self.separators
+                .iter()
+                .filter_map(|separator| {
+                    let (artist, title) = title.split_once(separator.as_str())?;
+                    (!artist.trim().is_empty() && !title.trim().is_empty())
+                        .then_some((Some(artist), title))
+                })
+                .min_by_key(|(artist, _)| artist.map_or(0, str::len))
+        };
+
+        captured.or_else(separated).unwrap_or((None, title))
+    }
+
+    fn clean(&self, text: &str) -> String {
+        if !self.strip_junk {
+            return text.trim().to_string();
+        }
+
+        let text = TRAILING_URL.replace(text, "");
+        JUNK_MARKERS.replace_all(&text, "").trim().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separators() {
+        let parser = TitleParser::default();
+
+        assert_eq!(
+            parser.parse("Daft Punk - One More Time", None),
+            TrackTags::mock("One More Time", Some("Daft Punk"))
+        );
+        assert_eq!(
+            parser.parse("Sigur Rós – Hoppípolla - Live", None),
+            TrackTags::mock("Hoppípolla - Live", Some("Sigur Rós"))
+        );
+        assert_eq!(
+            parser.parse(" - Title", None),
+            TrackTags::mock("- Title", None)
+        );
+        assert_eq!(
+            parser.parse("Just a title", None),
+            TrackTags::mock("Just a title", None)
+        );
+        // The stream already knows better
+        assert_eq!(
+            parser.parse("A - B", Some("Artist")),
+            TrackTags::mock("A - B", Some("Artist"))
+        );
+    }
+
+    #[test]
+    fn patterns() {
+        let parser = TitleParser {
+            patterns: vec![Regex::new(r"^(?<title>.+) by (?<artist>.+)$").unwrap()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            parser.parse("Blue Monday by New Order", None),
+            TrackTags::mock("Blue Monday", Some("New Order"))
+        );
+        assert_eq!(
+            parser.parse("New Order - Blue Monday", None),
+            TrackTags::mock("Blue Monday", Some("New Order"))
+        );
+    }
+
+    #[test]
+    fn garbled() {
+        let parser = TitleParser {
+            encoding: charset::for_label("windows-1251"),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            parser.parse("Êèíî - Ãðóïïà êðîâè", None),
+            TrackTags::mock("Группа крови", Some("Кино"))
+        );
+    }
+
+    #[test]
+    fn junk() {
+        let parser = TitleParser {
+            strip_junk: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            parser.parse(
+                "Artist - Song (Radio Edit) [Explicit] - www.radio.example",
+                None
+            ),
+            TrackTags::mock("Song", Some("Artist"))
+        );
+        assert_eq!(
+            parser.parse(
+                "Artist - Song (2011 Remaster) https://radio.example/now",
+                None
+            ),
+            TrackTags::mock("Song", Some("Artist"))
+        );
+        assert_eq!(
+            parser.parse("Artist - Song (Live)", None),
+            TrackTags::mock("Song (Live)", Some("Artist"))
+        );
+    }
+}